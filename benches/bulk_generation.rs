@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use image::Rgb;
+use zxingcpp::BarcodeFormat;
+
+use app_lib::generator::{
+    barcode_config::{BarcodeConfigBuilder, TextPosition},
+    bulk_generator::BulkGenerator,
+    bulk_generator_config::{BulkGeneratorConfig, BulkOutputKind, BulkSource},
+    importer::BarcodeImportRowCSV,
+    layout::Layout,
+    templates::Template,
+};
+
+const LABELS: usize = 10_000;
+
+fn rows() -> Vec<BarcodeImportRowCSV> {
+    (0..LABELS)
+        .map(|i| BarcodeImportRowCSV {
+            value: format!("SKU-{:08}", i),
+            upper_center_text: String::new(),
+            lower_center_text: format!("Item {}", i),
+            scale: None,
+            height_percentage: None,
+            width_percentage: None,
+            font_size: None,
+        })
+        .collect()
+}
+
+// Renders 10k Code128 labels with one worker and with one worker per core.
+// Paged output keeps file writes out of the measurement
+fn bulk_generation(c: &mut Criterion) {
+    let config = BarcodeConfigBuilder::new()
+        .set_format(BarcodeFormat::Code128)
+        .add_text("", Rgb([0, 0, 0]), 20, TextPosition::LowerCenter)
+        .build();
+    let template = Template::new(config, "Bench".to_string(), String::new());
+    let rows = rows();
+
+    let mut group = c.benchmark_group("bulk_code128");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(LABELS as u64));
+    for workers in [1, 0] {
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_kind = BulkOutputKind::Paged(Layout::new());
+        config.workers = workers;
+        let generator = BulkGenerator::new(config);
+        let name = if workers == 1 {
+            "sequential"
+        } else {
+            "parallel"
+        };
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                generator
+                    .generate_barcodes(&template, rows.clone())
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bulk_generation);
+criterion_main!(benches);
@@ -1,3 +1,5 @@
 fn main() {
+    // Only the desktop app needs the Tauri config and resources
+    #[cfg(feature = "app")]
     tauri_build::build()
 }
@@ -1,4 +1,5 @@
 import React from 'react';
+import type { Grade } from '../lib/interfaces';
 
 interface BarcodePreviewProps {
     generatedBarcode: string;
@@ -6,6 +7,8 @@ interface BarcodePreviewProps {
     isGenerating: boolean;
     isDownloading: boolean;
     downloadSuccess: boolean;
+    // Print quality estimate, null for matrix codes
+    qualityGrade: Grade | null;
     onGenerate: () => void;
     onDownload: () => void;
 }
@@ -16,6 +19,7 @@ const BarcodePreview: React.FC<BarcodePreviewProps> = ({
     isGenerating,
     isDownloading,
     downloadSuccess,
+    qualityGrade,
     onGenerate,
     onDownload
 }) => {
@@ -41,6 +45,9 @@ const BarcodePreview: React.FC<BarcodePreviewProps> = ({
                             alt="Generated barcode"
                             className="max-w-full max-h-96 border border-gray-200 rounded-lg"
                         />
+                        {qualityGrade && (
+                            <p className="mt-2 text-sm text-gray-600">Estimated print quality grade: {qualityGrade}</p>
+                        )}
                         <button
                             onClick={onDownload}
                             disabled={isDownloading}
@@ -0,0 +1,65 @@
+import React from 'react';
+import type { BarcodeColors, RgbColor } from '../lib/interfaces';
+
+interface ColorsConfigProps {
+    colors: BarcodeColors;
+    transparentBackground: boolean;
+    onColorsChange: (colors: BarcodeColors) => void;
+    onTransparentBackgroundChange: (transparent: boolean) => void;
+}
+
+const ColorsConfig: React.FC<ColorsConfigProps> = ({
+    colors,
+    transparentBackground,
+    onColorsChange,
+    onTransparentBackgroundChange
+}) => {
+    const handleChange = (target: keyof BarcodeColors, field: keyof RgbColor, value: number) => {
+        onColorsChange({
+            ...colors,
+            [target]: { ...colors[target], [field]: value }
+        });
+    };
+
+    const renderColor = (target: keyof BarcodeColors, label: string) => (
+        <div>
+            <label className="block text-sm font-medium text-gray-700 mb-1">{label}</label>
+            <div className="grid grid-cols-3 gap-2">
+                {(['r', 'g', 'b'] as (keyof RgbColor)[]).map(field => (
+                    <div key={field}>
+                        <label className="block text-xs text-gray-600">{field.toUpperCase()}</label>
+                        <input
+                            type="number"
+                            value={colors[target][field]}
+                            onChange={(e) => handleChange(target, field, parseInt(e.target.value))}
+                            min="0"
+                            max="255"
+                            className="w-full px-2 py-1 border border-gray-300 rounded text-sm focus:border-blue-500 focus-ring"
+                        />
+                    </div>
+                ))}
+            </div>
+        </div>
+    );
+
+    return (
+        <div className="mb-6">
+            <h3 className="text-lg font-semibold text-gray-900 mb-4">Colors</h3>
+            <div className="grid grid-cols-2 gap-4">
+                {renderColor('foreground', 'Bars')}
+                {renderColor('background', 'Background')}
+            </div>
+            <div className="flex items-center space-x-2 mt-3">
+                <input
+                    type="checkbox"
+                    checked={transparentBackground}
+                    onChange={(e) => onTransparentBackgroundChange(e.target.checked)}
+                    className="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 focus:ring-2"
+                />
+                <label className="font-normal">Transparent background (PNG)</label>
+            </div>
+        </div>
+    );
+};
+
+export default ColorsConfig;
@@ -1,17 +1,23 @@
 import React from 'react';
-import type { BarcodeDimensions } from '../lib/interfaces';
+import { LengthUnit, type BarcodeDimensions, type ModuleSizing, type XDimension } from '../lib/interfaces';
 import { validationRules } from '../lib/validationRules';
 
 interface DimensionsConfigProps {
     dimensions: BarcodeDimensions;
     format: string;
     onDimensionsChange: (field: keyof BarcodeDimensions, value: number) => void;
+    xDimension: XDimension | null;
+    moduleSizing: ModuleSizing | null;
+    onXDimensionChange: (xDimension: XDimension | null) => void;
 }
 
 const DimensionsConfig: React.FC<DimensionsConfigProps> = ({
     dimensions,
     format,
-    onDimensionsChange
+    onDimensionsChange,
+    xDimension,
+    moduleSizing,
+    onXDimensionChange
 }) => {
     const getCurrentValidationRule = () => {
         return validationRules.find(rule => rule.format === format);
@@ -92,6 +98,43 @@ const DimensionsConfig: React.FC<DimensionsConfigProps> = ({
                     )}
                 </div>
             </div>
+            <div className="mt-4">
+                <div className="flex items-center space-x-2">
+                    <input
+                        type="checkbox"
+                        checked={xDimension !== null}
+                        onChange={(e) => onXDimensionChange(e.target.checked ? { value: 0.33, unit: LengthUnit.Mm } : null)}
+                        className="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 focus:ring-2"
+                    />
+                    <label className="font-normal">Size modules from X-dimension (width follows the data)</label>
+                </div>
+                {xDimension && (
+                    <div className="grid grid-cols-2 gap-4 mt-2">
+                        <input
+                            type="number"
+                            value={xDimension.value}
+                            onChange={(e) => onXDimensionChange({ ...xDimension, value: parseFloat(e.target.value) })}
+                            step="0.01"
+                            min="0"
+                            className="w-full px-3 py-3 border border-gray-300 rounded-lg text-base focus:border-blue-500 focus-ring"
+                        />
+                        <select
+                            value={xDimension.unit}
+                            onChange={(e) => onXDimensionChange({ ...xDimension, unit: e.target.value as LengthUnit })}
+                            className="w-full px-3 py-3 border border-gray-300 rounded-lg text-base focus:border-blue-500 focus-ring"
+                        >
+                            <option value={LengthUnit.Mm}>mm</option>
+                            <option value={LengthUnit.Mils}>mils</option>
+                        </select>
+                    </div>
+                )}
+                {xDimension && moduleSizing && (
+                    <p className="text-xs text-gray-500 mt-1">
+                        {moduleSizing.pixels_per_module} px per module, X-dimension {moduleSizing.x_dimension_mm.toFixed(3)} mm,
+                        printed size {moduleSizing.width_mm.toFixed(2)} x {moduleSizing.height_mm.toFixed(2)} mm
+                    </p>
+                )}
+            </div>
         </div>
     );
 };
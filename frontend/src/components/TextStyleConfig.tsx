@@ -1,6 +1,6 @@
 import React from 'react';
 import type { BarcodeTextStyleConfig, RgbColor } from '../lib/interfaces';
-import { TextPosition } from '../lib/interfaces';
+import { TextAlign, TextPosition } from '../lib/interfaces';
 
 interface TextStyleConfigProps {
     texts: BarcodeTextStyleConfig[];
@@ -55,10 +55,10 @@ const TextStyleConfig: React.FC<TextStyleConfigProps> = ({
                         <div className="space-y-3">
                             <div>
                                 <label className="block text-sm font-medium text-gray-700 mb-1">Text Content</label>
-                                <input
-                                    type="text"
+                                <textarea
                                     value={textConfig.text}
                                     onChange={(e) => onTextChange(index, 'text', e.target.value)}
+                                    rows={2}
                                     className="w-full px-3 py-2 border border-gray-300 rounded-lg text-sm focus:border-blue-500 focus-ring"
                                 />
                             </div>
@@ -98,6 +98,33 @@ const TextStyleConfig: React.FC<TextStyleConfigProps> = ({
                                     ))}
                                 </select>
                             </div>
+                            <div className="grid grid-cols-2 gap-3">
+                                <div>
+                                    <label className="block text-sm font-medium text-gray-700 mb-1">Alignment</label>
+                                    <select
+                                        value={textConfig.alignment ?? ''}
+                                        onChange={(e) => onTextChange(index, 'alignment', e.target.value ? e.target.value as TextAlign : null)}
+                                        className="w-full px-3 py-2 border border-gray-300 rounded-lg text-sm focus:border-blue-500 focus-ring"
+                                    >
+                                        <option value="">From position</option>
+                                        {Object.values(TextAlign).map(alignment => (
+                                            <option key={alignment} value={alignment}>{alignment}</option>
+                                        ))}
+                                    </select>
+                                </div>
+                                <div>
+                                    <label className="block text-sm font-medium text-gray-700 mb-1">Line Spacing</label>
+                                    <input
+                                        type="number"
+                                        value={textConfig.line_spacing}
+                                        onChange={(e) => onTextChange(index, 'line_spacing', parseFloat(e.target.value))}
+                                        min="0.5"
+                                        max="3"
+                                        step="0.1"
+                                        className="w-full px-3 py-2 border border-gray-300 rounded-lg text-sm focus:border-blue-500 focus-ring"
+                                    />
+                                </div>
+                            </div>
                             <div>
                                 <label className="block text-sm font-medium text-gray-700 mb-1">Font</label>
                                 <input
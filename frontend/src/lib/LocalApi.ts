@@ -1,11 +1,20 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { BarcodeConfig, BarcodeImportRowCSV, JsonResponse, Layout, Template } from "./interfaces";
+import type { BarcodeConfig, BarcodeImportRowCSV, BulkOutputKind, Grade, JsonResponse, LabelStock, Layout, ModuleSizing, PackingReport, QualityReport, Template } from "./interfaces";
 
 export const LocalApi = {
     generateBarcode: async (config: BarcodeConfig) => {
         const result = await invoke('generate_barcode', { config });
         return result as ArrayBuffer;
     },
+    measureBarcode: async (config: BarcodeConfig) => {
+        // Data is null unless the config has an X-dimension
+        const result = await invoke('measure_barcode', { config });
+        return result as JsonResponse<ModuleSizing | null>;
+    },
+    gradeBarcode: async (config: BarcodeConfig) => {
+        const result = await invoke('grade_barcode', { config });
+        return result as JsonResponse<QualityReport>;
+    },
     getLayout: async () => {
         const result = await invoke('get_layout');
         return result as JsonResponse<Layout>;
@@ -33,5 +42,36 @@ export const LocalApi = {
     getImportedBarcodes: async () => {
         const result = await invoke('get_imported_barcodes')
         return result as JsonResponse<BarcodeImportRowCSV[]>
+    },
+    generateBulk: async (templateId: string, outputDir: string, outputKind: BulkOutputKind, verify: boolean = false, minGrade: Grade | null = null) => {
+        // Data is the job id, the BulkGenerationSummary arrives with the job-finished event.
+        // With verify, rows which don't read back are reported as failed, as are rows graded below minGrade
+        const result = await invoke('generate_bulk', { templateId, outputDir, outputKind, verify, minGrade })
+        return result as JsonResponse<string>
+    },
+    getLabelStocks: async () => {
+        const result = await invoke('get_label_stocks')
+        return result as JsonResponse<LabelStock[]>
+    },
+    previewLayout: async (layout: Layout) => {
+        const result = await invoke('preview_layout', { layout })
+        return result as ArrayBuffer
+    },
+    layoutReport: async (layout: Layout) => {
+        const result = await invoke('layout_report', { layout })
+        return result as JsonResponse<PackingReport>
+    },
+    exportLayout: async (layout: Layout, outputPath: string) => {
+        // Data is the job id, the written file paths arrive with the job-finished event
+        const result = await invoke('export_layout', { layout, outputPath })
+        return result as JsonResponse<string>
+    },
+    cancelJob: async (jobId: string) => {
+        const result = await invoke('cancel_job', { jobId })
+        return result as JsonResponse<void>
+    },
+    addFontDirectory: async (path: string) => {
+        const result = await invoke('add_font_directory', { path })
+        return result as JsonResponse<void>
     }
 }
\ No newline at end of file
@@ -5,6 +5,54 @@ export interface BarcodeConfig {
     quiet_zones: boolean;
     dimensions: BarcodeDimensions;
     data: string;
+    colors: BarcodeColors;
+    transparent_background: boolean;
+    // Encoded data printed under linear codes to the symbology spec, null prints nothing
+    human_readable: HumanReadableConfig | null;
+    // Whole pixels per module instead of stretching to width_mm, null stretches
+    x_dimension: XDimension | null;
+    // Taken off every bar for printers that spread ink, null leaves bars as they are
+    bar_width_reduction: BarWidthReduction | null;
+}
+
+export enum ReductionUnit {
+    Micrometers = "Micrometers",
+    Pixels = "Pixels"
+}
+
+export interface BarWidthReduction {
+    value: number;
+    unit: ReductionUnit;
+}
+
+export enum LengthUnit {
+    Mm = "Mm",
+    Mils = "Mils"
+}
+
+export interface XDimension {
+    value: number;
+    unit: LengthUnit;
+}
+
+// Printed size reported by measure_barcode when an X-dimension is set
+export interface ModuleSizing {
+    pixels_per_module: number;
+    x_dimension_mm: number;
+    symbol_width_mm: number;
+    width_mm: number;
+    height_mm: number;
+}
+
+export interface HumanReadableConfig {
+    font: string;
+    text_size: number;
+    margin: number;
+}
+
+export interface BarcodeColors {
+    foreground: RgbColor;
+    background: RgbColor;
 }
 
 export function createDefaultBarcodeConfig(): BarcodeConfig {
@@ -17,6 +65,8 @@ export function createDefaultBarcodeConfig(): BarcodeConfig {
             text_position: TextPosition.Lower,
             font: 'Arial',
             margin: 5,
+            alignment: null,
+            line_spacing: 1,
             id: Date.now()
         }],
         scale: 2,
@@ -27,7 +77,15 @@ export function createDefaultBarcodeConfig(): BarcodeConfig {
             width_mm: 50,
             height_mm: 25
         },
-        data: '123456789'
+        data: '123456789',
+        colors: {
+            foreground: { r: 0, g: 0, b: 0 },
+            background: { r: 255, g: 255, b: 255 }
+        },
+        transparent_background: false,
+        human_readable: null,
+        x_dimension: null,
+        bar_width_reduction: null
     }
 }
 
@@ -45,6 +103,9 @@ export interface BarcodeTextStyleConfig {
     text_position: TextPosition;
     font: string;
     margin: number;
+    // null lets the position decide
+    alignment: TextAlign | null;
+    line_spacing: number;
     id: number;
 }
 
@@ -93,6 +154,12 @@ export enum TextPosition {
     LowerCenter = "LowerCenter"
 }
 
+export enum TextAlign {
+    Left = "Left",
+    Center = "Center",
+    Right = "Right"
+}
+
 export function createBarcodeFormatWrapper(format: BarcodeFormat): BarcodeFormatWrapper {
     return { format };
 }
@@ -134,16 +201,165 @@ export interface JsonResponse<T> {
     data?: T;
 }
 
+export type Paper =
+    | "A0" | "A1" | "A2" | "A3" | "A4" | "A5" | "A6" | "A7"
+    | "B0" | "B1" | "B2" | "B3" | "B4" | "B5" | "B6"
+    | "Letter" | "Legal" | "Tabloid"
+    | { Custom: { width_mm: number; height_mm: number } };
+
+export type Orientation = "Portrait" | "Landscape";
+
+export type ExportFile = "PDF" | "PNG";
+
+// Sheet of pre-cut labels, pitch is label size plus the gap to the next label
+export interface LabelStock {
+    id: string;
+    name: string;
+    paper: Paper;
+    orientation: Orientation;
+    label_width_mm: number;
+    label_height_mm: number;
+    rows: number;
+    cols: number;
+    pitch_x_mm: number;
+    pitch_y_mm: number;
+    margin_top_mm: number;
+    margin_left_mm: number;
+    corner_radius_mm: number;
+}
+
+// Where the block of cells sits between the margins
+export type Alignment = "Start" | "Center" | "End";
+
+export interface Margins {
+    top_mm: number;
+    right_mm: number;
+    bottom_mm: number;
+    left_mm: number;
+}
+
+// Grid scales every barcode into cells of the first one, shelf keeps their own sizes
+export type Packing = "Grid" | "Shelf";
+
+export interface PageUtilisation {
+    page: number;
+    barcodes: number;
+    // Share of the space between the margins covered by barcodes, 0 to 1
+    utilisation: number;
+}
+
+export interface PackingReport {
+    pages: PageUtilisation[];
+    utilisation: number;
+}
+
 export interface Layout {
     config: BarcodeConfig;
+    max_rows: number;
+    max_cols: number;
+    paper: Paper;
+    orientation: Orientation;
+    // Resolution of the page pixels, bulk exports use the generation DPI
+    dpi: number;
+    // Centers the cells on the whole page, margins, gutters and alignment are then ignored
+    auto_margin: boolean;
+    margins: Margins;
+    gutter_x_mm: number;
+    gutter_y_mm: number;
+    horizontal_alignment: Alignment;
+    vertical_alignment: Alignment;
+    packing: Packing;
+    // Cells of the first page left empty for partly used label sheets, counted from 0 row by row
+    start_cell: number;
+    skipped_cells: number[];
+    grid_borders: boolean;
+    export_file: ExportFile;
+    // Barcodes go on the labels of this sheet instead of packed on the paper
+    label_stock: LabelStock | null;
+}
+
+export type BulkOutputKind = "IndividualPng" | "IndividualSvg" | { Paged: Layout };
+
+// ISO/IEC 15416 style print quality estimate of a linear barcode
+export enum Grade {
+    A = "A",
+    B = "B",
+    C = "C",
+    D = "D",
+    F = "F"
+}
+
+export interface ParameterGrades {
+    symbol_contrast: Grade;
+    min_reflectance: Grade;
+    min_edge_contrast: Grade;
+    modulation: Grade;
+    defects: Grade;
+    decodability: Grade;
+    edge_determination: Grade;
+    quiet_zone: Grade;
+}
+
+export interface ScanReport {
+    symbol_contrast: number;
+    min_reflectance: number;
+    min_edge_contrast: number;
+    modulation: number;
+    defects: number;
+    decodability: number;
+    edges_found: number;
+    edges_expected: number;
+    quiet_zone_left: number;
+    quiet_zone_right: number;
+    quiet_zone_required: number;
+    grades: ParameterGrades;
+    grade: Grade;
+}
+
+export interface QualityReport {
+    scans: ScanReport[];
+    grade: Grade;
+}
+
+export interface ValidationError {
+    field: string;
+    position: number | null;
+    reason: unknown;
+}
+
+export interface FailedRow {
+    row: number;
+    value: string;
+    errors: ValidationError[];
+}
+
+export interface BulkGenerationSummary {
+    generated: number;
+    failed: FailedRow[];
+}
+
+// Payload of the job-progress event
+export interface JobProgress {
+    job_id: string;
+    rows_total: number;
+    rows_done: number;
+    rows_failed: number;
+    current_page: number;
+}
+
+// Payload of the job-finished event, response data depends on the job
+export interface JobFinished<T> {
+    job_id: string;
+    cancelled: boolean;
+    response: JsonResponse<T>;
 }
 
 export interface BarcodeImportRowCSV {
     value: string;
     upper_center_text: string;
     lower_center_text: string;
-    scale: number;
-    height_percentage: number;
-    width_percentage: number;
-    font_size: number;
+    scale: number | null;
+    height_percentage: number | null;
+    width_percentage: number | null;
+    font_size: number | null;
 }
@@ -1,20 +1,24 @@
 import React, { useState, useEffect } from 'react';
 import { save } from '@tauri-apps/plugin-dialog';
 import { writeFile } from '@tauri-apps/plugin-fs';
-import { BarcodeFormat, TextPosition, type BarcodeConfig, type BarcodeDimensions, type BarcodeTextStyleConfig, type RgbColor, createBarcodeFormatWrapper, getAvailableBarcodeFormats, createDefaultBarcodeConfig } from '../lib/interfaces';
+import { BarcodeFormat, ReductionUnit, TextPosition, type BarcodeConfig, type BarcodeDimensions, type BarcodeTextStyleConfig, type Grade, type ModuleSizing, type RgbColor, createBarcodeFormatWrapper, getAvailableBarcodeFormats, createDefaultBarcodeConfig } from '../lib/interfaces';
 import { validationRules } from '../lib/validationRules';
 import ValidationNotification from '../components/ValidationNotification';
 import BarcodeDataInput from '../components/BarcodeDataInput';
 import DimensionsConfig from '../components/DimensionsConfig';
+import ColorsConfig from '../components/ColorsConfig';
 import TextStyleConfig from '../components/TextStyleConfig';
 import BarcodePreview from '../components/BarcodePreview';
 import TemplateManager from '../components/TemplateManager';
+import { LocalApi } from '../lib/LocalApi';
 import { getBarcodeResultAsBase64DataUrl, validateBarcodeData } from '../lib/utils';
 
 const Home: React.FC = () => {
     const [config, setConfig] = useState<BarcodeConfig>(createDefaultBarcodeConfig());
 
     const [generatedBarcode, setGeneratedBarcode] = useState<string>('');
+    const [moduleSizing, setModuleSizing] = useState<ModuleSizing | null>(null);
+    const [qualityGrade, setQualityGrade] = useState<Grade | null>(null);
     const [autoGenerate, setAutoGenerate] = useState<boolean>(true);
     const [isGenerating, setIsGenerating] = useState<boolean>(false);
     const [validationErrors, setValidationErrors] = useState<string[]>([]);
@@ -144,6 +148,8 @@ const Home: React.FC = () => {
                 text_position: TextPosition.Lower,
                 font: 'Arial',
                 margin: 5,
+                alignment: null,
+                line_spacing: 1,
                 id: Date.now()
             }]
         }));
@@ -195,6 +201,10 @@ const Home: React.FC = () => {
             setIsGenerating(true);
             const dataUrl = await getBarcodeResultAsBase64DataUrl(config);
             setGeneratedBarcode(dataUrl);
+            const measured = await LocalApi.measureBarcode(config);
+            setModuleSizing(measured.success ? measured.data ?? null : null);
+            const graded = await LocalApi.gradeBarcode(config);
+            setQualityGrade(graded.success ? graded.data?.grade ?? null : null);
         } catch (error) {
             console.error('Error generating barcode:', error);
             showValidationNotification(['Error generating barcode. Please check your configuration.']);
@@ -324,10 +334,87 @@ const Home: React.FC = () => {
                         </div>
                     </div>
 
+                    <div className="mb-6">
+                        <label className="block font-medium text-gray-700 mb-2">Bar Width Reduction</label>
+                        <div className="flex items-center space-x-2">
+                            <input
+                                type="checkbox"
+                                checked={config.bar_width_reduction !== null}
+                                onChange={(e) => handleConfigChange('bar_width_reduction', e.target.checked
+                                    ? { value: 0, unit: ReductionUnit.Micrometers }
+                                    : null)}
+                                className="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 focus:ring-2"
+                            />
+                            <label className="font-normal">Narrow bars to compensate for print gain</label>
+                        </div>
+                        {config.bar_width_reduction && (
+                            <div className="grid grid-cols-2 gap-4 mt-2">
+                                <input
+                                    type="number"
+                                    value={config.bar_width_reduction.value}
+                                    onChange={(e) => handleConfigChange('bar_width_reduction', {
+                                        ...config.bar_width_reduction!,
+                                        value: parseFloat(e.target.value)
+                                    })}
+                                    min="0"
+                                    className="w-full px-3 py-3 border border-gray-300 rounded-lg text-base focus:border-blue-500 focus-ring"
+                                />
+                                <select
+                                    value={config.bar_width_reduction.unit}
+                                    onChange={(e) => handleConfigChange('bar_width_reduction', {
+                                        ...config.bar_width_reduction!,
+                                        unit: e.target.value as ReductionUnit
+                                    })}
+                                    className="w-full px-3 py-3 border border-gray-300 rounded-lg text-base focus:border-blue-500 focus-ring"
+                                >
+                                    <option value={ReductionUnit.Micrometers}>µm</option>
+                                    <option value={ReductionUnit.Pixels}>px</option>
+                                </select>
+                            </div>
+                        )}
+                    </div>
+
+                    <div className="mb-6">
+                        <label className="block font-medium text-gray-700 mb-2">Human Readable Text</label>
+                        <div className="flex items-center space-x-2">
+                            <input
+                                type="checkbox"
+                                checked={config.human_readable !== null}
+                                onChange={(e) => handleConfigChange('human_readable', e.target.checked
+                                    ? { font: 'DejaVuSans', text_size: 20, margin: 2 }
+                                    : null)}
+                                className="w-4 h-4 text-blue-600 bg-gray-100 border-gray-300 rounded focus:ring-blue-500 focus:ring-2"
+                            />
+                            <label className="font-normal">Print the encoded data under linear codes</label>
+                        </div>
+                        {config.human_readable && (
+                            <input
+                                type="number"
+                                value={config.human_readable.text_size}
+                                onChange={(e) => handleConfigChange('human_readable', {
+                                    ...config.human_readable!,
+                                    text_size: parseInt(e.target.value)
+                                })}
+                                min="1"
+                                className="w-full mt-2 px-3 py-3 border border-gray-300 rounded-lg text-base focus:border-blue-500 focus-ring"
+                            />
+                        )}
+                    </div>
+
                     <DimensionsConfig
                         dimensions={config.dimensions}
                         format={config.format.format}
                         onDimensionsChange={handleDimensionsChange}
+                        xDimension={config.x_dimension}
+                        moduleSizing={moduleSizing}
+                        onXDimensionChange={(xDimension) => handleConfigChange('x_dimension', xDimension)}
+                    />
+
+                    <ColorsConfig
+                        colors={config.colors}
+                        transparentBackground={config.transparent_background}
+                        onColorsChange={(colors) => handleConfigChange('colors', colors)}
+                        onTransparentBackgroundChange={(transparent) => handleConfigChange('transparent_background', transparent)}
                     />
 
                     <TextStyleConfig
@@ -355,6 +442,7 @@ const Home: React.FC = () => {
                     isGenerating={isGenerating}
                     isDownloading={isDownloading}
                     downloadSuccess={downloadSuccess}
+                    qualityGrade={qualityGrade}
                     onGenerate={generateBarcode}
                     onDownload={downloadBarcode}
                 />
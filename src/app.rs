@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{ipc::Response, State};
+use tauri::{App, AppHandle, Emitter, Manager};
+
+use crate::generator::importer::BarcodeImportRowCSV;
+use crate::generator::{
+    barcode_config::BarcodeConfig,
+    bulk_generator::BulkGenerationError,
+    bulk_generator_config::{BulkGeneratorConfig, BulkOutputKind, BulkSource},
+    fonts::FontRegistry,
+    frontend_interface::{FrontendInterface, JsonResponse},
+    generator::GeneratedBarcode,
+    job::Job,
+    layout::Layout,
+    quality::Grade,
+    templates::{self, Template},
+};
+
+use serde_json;
+
+#[derive(Clone)]
+struct AppState {
+    frontend: FrontendInterface,
+    imported_barcodes: Vec<BarcodeImportRowCSV>,
+    // Last layout used for preview or export
+    layout: Layout,
+    // Barcodes from the last bulk generation, placed on pages by preview and export.
+    // Shared so export tasks don't copy every image buffer
+    generated_barcodes: Arc<[GeneratedBarcode]>,
+    // DPI the generated barcodes were rendered at, their pages are laid out at it
+    generated_dpi: f32,
+    // Bulk and export jobs running in the background, by job id
+    jobs: HashMap<String, Job>,
+}
+
+// Payload of the job-finished event, response is what the command would have returned
+#[derive(Clone, Serialize)]
+struct JobFinished {
+    job_id: String,
+    cancelled: bool,
+    response: JsonResponse,
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_fs::init())
+        .invoke_handler(tauri::generate_handler![
+            generate_barcode,
+            measure_barcode,
+            grade_barcode,
+            save_template,
+            get_templates,
+            get_template,
+            delete_template,
+            import_barcodes_csv,
+            get_imported_barcodes,
+            generate_bulk,
+            get_layout,
+            get_label_stocks,
+            preview_layout,
+            layout_report,
+            export_layout,
+            cancel_job,
+            add_font_directory
+        ])
+        .setup(|app| {
+            app.manage(Mutex::new(AppState {
+                frontend: FrontendInterface::new(),
+                imported_barcodes: vec![],
+                layout: Layout::new(),
+                generated_barcodes: Arc::from([]),
+                generated_dpi: Layout::new().dpi,
+                jobs: HashMap::new(),
+            }));
+            // Users can drop fonts into the app data fonts folder
+            if let Ok(app_data_dir) = app.path().app_data_dir() {
+                FontRegistry::global().add_font_dir(app_data_dir.join("fonts"));
+            }
+            if cfg!(debug_assertions) {
+                app.handle().plugin(
+                    tauri_plugin_log::Builder::default()
+                        .level(log::LevelFilter::Info)
+                        .build(),
+                )?;
+            }
+            Ok(())
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[tauri::command]
+fn get_templates(state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.get_templates()
+}
+
+#[tauri::command]
+fn get_template(id: String, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.get_template(id)
+}
+
+#[tauri::command]
+fn delete_template(id: String, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.delete_template(id)
+}
+
+// Save the template to the templates folder
+#[tauri::command]
+fn save_template(template: Template, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.save_template(template)
+}
+
+#[tauri::command]
+fn generate_barcode(config: BarcodeConfig, state: State<'_, Mutex<AppState>>) -> Response {
+    let state = state.lock().unwrap();
+    let generated_barcode_bytes = state.frontend.generate_barcode(config);
+    match generated_barcode_bytes {
+        Ok(generated_barcode_bytes) => tauri::ipc::Response::new(generated_barcode_bytes),
+        Err(e) => {
+            log::error!("Error generating barcode: {}", e);
+            return Response::new(e.to_string());
+        }
+    }
+}
+
+#[tauri::command]
+fn measure_barcode(config: BarcodeConfig, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.measure_barcode(config)
+}
+
+#[tauri::command]
+fn grade_barcode(config: BarcodeConfig, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.grade_barcode(config)
+}
+
+#[tauri::command]
+fn get_imported_barcodes(state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    JsonResponse {
+        success: true,
+        message: "OK".to_string(),
+        data: Some(serde_json::to_value(&state.imported_barcodes).unwrap()),
+    }
+}
+
+#[tauri::command]
+fn import_barcodes_csv(file_bytes: Vec<u8>, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let mut state = state.lock().unwrap();
+    let import_result = state.frontend.import_from_csv(file_bytes);
+    match import_result {
+        Ok(result) => {
+            state.imported_barcodes = result;
+            JsonResponse {
+                success: true,
+                message: "Ok".to_string(),
+                data: None,
+            }
+        }
+        Err(e) => JsonResponse {
+            success: false,
+            message: e.to_string(),
+            data: None,
+        },
+    }
+}
+
+// Create a job which sends its progress to the frontend as job-progress events
+fn start_job(app: &AppHandle, state: &mut AppState) -> Job {
+    let app = app.clone();
+    let job = Job::new().on_progress(move |progress| {
+        let _ = app.emit("job-progress", progress);
+    });
+    state.jobs.insert(job.id.clone(), job.clone());
+    job
+}
+
+fn finish_job(app: &AppHandle, job: &Job, response: JsonResponse) {
+    let state = app.state::<Mutex<AppState>>();
+    state.lock().unwrap().jobs.remove(&job.id);
+    let _ = app.emit(
+        "job-finished",
+        JobFinished {
+            job_id: job.id.clone(),
+            cancelled: job.is_cancelled(),
+            response,
+        },
+    );
+}
+
+fn job_started(job: &Job) -> JsonResponse {
+    JsonResponse {
+        success: true,
+        message: "Job started".to_string(),
+        data: Some(serde_json::to_value(&job.id).unwrap()),
+    }
+}
+
+// Generate the imported barcodes with a template on a background task. Individual outputs are
+// written to output_dir, paged output also writes the sheets there. With verify every barcode is
+// read back first and rows which don't decode fail, rows graded below min_grade fail too.
+// Returns the job id, the result arrives with the job-finished event
+#[tauri::command]
+fn generate_bulk(
+    template_id: String,
+    output_dir: String,
+    output_kind: BulkOutputKind,
+    verify: bool,
+    min_grade: Option<Grade>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> JsonResponse {
+    let mut state = state.lock().unwrap();
+    let mut config = BulkGeneratorConfig::new(
+        BulkSource::Rows(state.imported_barcodes.clone()),
+        template_id,
+    );
+    // Sheets are rendered at the DPI of their layout
+    if let BulkOutputKind::Paged(layout) = &output_kind {
+        state.layout = layout.clone();
+        config.dpi = layout.dpi;
+    }
+    let dpi = config.dpi;
+    config.output_dir = output_dir;
+    config.output_kind = output_kind;
+    config.verify = verify;
+    config.min_grade = min_grade;
+    let frontend = state.frontend.clone();
+    let job = start_job(&app, &mut state);
+    let response = job_started(&job);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = match frontend.generate_bulk(config, Some(job.clone())) {
+            Ok(result) => {
+                let data = result.summary();
+                let state = app.state::<Mutex<AppState>>();
+                let mut state = state.lock().unwrap();
+                state.generated_barcodes = result.barcodes.into();
+                state.generated_dpi = dpi;
+                JsonResponse {
+                    success: true,
+                    message: "Ok".to_string(),
+                    data: Some(data),
+                }
+            }
+            // A job stopping part way still reports the rows it got through
+            Err(e) => JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: e
+                    .downcast_ref::<BulkGenerationError>()
+                    .map(|e| e.partial.summary()),
+            },
+        };
+        finish_job(&app, &job, response);
+    });
+    response
+}
+
+#[tauri::command]
+fn get_layout(state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    JsonResponse {
+        success: true,
+        message: "OK".to_string(),
+        data: Some(serde_json::to_value(&state.layout).unwrap()),
+    }
+}
+
+#[tauri::command]
+fn get_label_stocks(state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    state.frontend.get_label_stocks()
+}
+
+// Preview the first page of the layout with the last generated barcodes
+#[tauri::command]
+fn preview_layout(mut layout: Layout, state: State<'_, Mutex<AppState>>) -> Response {
+    let mut state = state.lock().unwrap();
+    layout.dpi = state.generated_dpi;
+    state.layout = layout.clone();
+    let preview = state
+        .frontend
+        .preview_layout(layout, &state.generated_barcodes);
+    match preview {
+        Ok(preview) => Response::new(preview),
+        Err(e) => {
+            log::error!("Error creating layout preview: {}", e);
+            Response::new(e.to_string())
+        }
+    }
+}
+
+// Page count and utilisation of the layout with the last generated barcodes
+#[tauri::command]
+fn layout_report(mut layout: Layout, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    layout.dpi = state.generated_dpi;
+    state
+        .frontend
+        .layout_report(layout, &state.generated_barcodes)
+}
+
+// Write every page of the layout to the path chosen by the user on a background task.
+// Returns the job id, the written paths arrive with the job-finished event
+#[tauri::command]
+fn export_layout(
+    mut layout: Layout,
+    output_path: String,
+    app: AppHandle,
+    state: State<'_, Mutex<AppState>>,
+) -> JsonResponse {
+    let mut state = state.lock().unwrap();
+    layout.dpi = state.generated_dpi;
+    state.layout = layout.clone();
+    let barcodes = state.generated_barcodes.clone();
+    let frontend = state.frontend.clone();
+    let job = start_job(&app, &mut state);
+    let response = job_started(&job);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let response = frontend.export_layout(layout, &barcodes, &output_path, job.clone());
+        finish_job(&app, &job, response);
+    });
+    response
+}
+
+#[tauri::command]
+fn cancel_job(job_id: String, state: State<'_, Mutex<AppState>>) -> JsonResponse {
+    let state = state.lock().unwrap();
+    match state.jobs.get(&job_id) {
+        Some(job) => {
+            job.cancel();
+            JsonResponse {
+                success: true,
+                message: "Cancelling job".to_string(),
+                data: None,
+            }
+        }
+        None => JsonResponse {
+            success: false,
+            message: format!("No running job with id {}", job_id),
+            data: None,
+        },
+    }
+}
+
+// Fonts in the directory are used before the embedded and system fonts
+#[tauri::command]
+fn add_font_directory(path: String) -> JsonResponse {
+    if !std::path::Path::new(&path).is_dir() {
+        return JsonResponse {
+            success: false,
+            message: format!("{} is not a directory", path),
+            data: None,
+        };
+    }
+    FontRegistry::global().add_font_dir(path);
+    JsonResponse {
+        success: true,
+        message: "Font directory added".to_string(),
+        data: None,
+    }
+}
@@ -0,0 +1,336 @@
+// Headless command line access to the generator library, for CI pipelines and servers without a display.
+// Every command prints a JSON summary on stdout and exits with one of the codes below
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+
+use app_lib::generator::{
+    barcode_config::{is_linear, BarcodeConfig, BarcodeConfigInternal},
+    bulk_generator::{BulkGenerationError, BulkGenerationResult},
+    bulk_generator_config::{BulkGeneratorConfig, BulkOutputKind, BulkSource, ErrorPolicy},
+    exporting::{
+        label_stock,
+        packing::{Packing, PackingReport},
+    },
+    frontend_interface::{FrontendInterface, JsonResponse},
+    generator::Generator,
+    layout::{ExportFile, ExportManager, Layout},
+    quality::{Grade, QualityGrader},
+    templates,
+    verification::Verifier,
+};
+
+// Everything generated
+const EXIT_OK: u8 = 0;
+// The command failed, nothing or only part of the output was written
+const EXIT_FAILED: u8 = 1;
+// Bulk and export finished but some rows were skipped
+const EXIT_ROWS_FAILED: u8 = 3;
+
+fn main() -> ExitCode {
+    // clap exits with 2 on bad arguments
+    let matches = cli().get_matches();
+    let (response, code) = match matches.subcommand() {
+        Some(("generate", args)) => summarize(generate(args)),
+        Some(("bulk", args)) => summarize_bulk(bulk(args)),
+        Some(("export", args)) => summarize_bulk(export(args)),
+        _ => unreachable!("a subcommand is required"),
+    };
+    println!("{}", serde_json::to_string_pretty(&response).unwrap());
+    ExitCode::from(code)
+}
+
+fn cli() -> Command {
+    let template = Arg::new("template")
+        .long("template")
+        .help("Id of a template saved in templates.json in the working directory");
+    let csv = Arg::new("csv")
+        .long("csv")
+        .required(true)
+        .help("CSV file with one barcode per row");
+    let dpi = Arg::new("dpi")
+        .long("dpi")
+        .value_parser(value_parser!(f32))
+        .default_value("300");
+    let workers = Arg::new("workers")
+        .long("workers")
+        .value_parser(value_parser!(usize))
+        .default_value("0")
+        .help("Rows rendered in parallel, 0 uses one worker per CPU core");
+    let abort = Arg::new("abort")
+        .long("abort")
+        .action(ArgAction::SetTrue)
+        .help("Stop at the first row which fails instead of skipping it");
+    let verify = Arg::new("verify")
+        .long("verify")
+        .action(ArgAction::SetTrue)
+        .help("Read every barcode back and fail the ones which don't decode to their value");
+    let min_grade = Arg::new("min-grade")
+        .long("min-grade")
+        .value_parser(["A", "B", "C", "D"])
+        .help("Fail linear barcodes with a lower estimated print quality grade");
+
+    Command::new("barcode-cli")
+        .about("Generate barcodes, bulk CSV runs and paged exports without the desktop app")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("generate")
+                .about("Generate one barcode as PNG or SVG, chosen by the output extension")
+                .arg(template.clone().required_unless_present("config"))
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .conflicts_with("template")
+                        .help("JSON file with a barcode config, as saved in templates"),
+                )
+                .arg(
+                    Arg::new("data")
+                        .long("data")
+                        .help("Value to encode, replaces the data of the config"),
+                )
+                .arg(Arg::new("output").long("output").required(true))
+                .arg(dpi.clone())
+                .arg(verify.clone())
+                .arg(min_grade.clone()),
+        )
+        .subcommand(
+            Command::new("bulk")
+                .about("Generate a file per CSV row with a template")
+                .arg(template.clone().required(true))
+                .arg(csv.clone())
+                .arg(Arg::new("output-dir").long("output-dir").required(true))
+                .arg(
+                    Arg::new("svg")
+                        .long("svg")
+                        .action(ArgAction::SetTrue)
+                        .help("Write SVG files instead of PNG"),
+                )
+                .arg(
+                    Arg::new("file-name-pattern")
+                        .long("file-name-pattern")
+                        .default_value("{value}")
+                        .help("{value}, {row} and {template} are replaced per barcode"),
+                )
+                .arg(dpi.clone())
+                .arg(workers.clone())
+                .arg(abort.clone())
+                .arg(verify.clone())
+                .arg(min_grade.clone()),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Place the barcodes of a CSV on pages, written as one PDF or PNG per page")
+                .arg(template.required(true))
+                .arg(csv)
+                .arg(Arg::new("output-dir").long("output-dir").required(true))
+                .arg(
+                    Arg::new("layout")
+                        .long("layout")
+                        .help("JSON file with the page layout, the default fits A4"),
+                )
+                .arg(Arg::new("label-stock").long("label-stock").help(
+                    "Id of a label sheet preset, e.g. avery-l7160, to print one barcode per label",
+                ))
+                .arg(
+                    Arg::new("start-cell")
+                        .long("start-cell")
+                        .value_parser(value_parser!(u32))
+                        .help("First cell used on the first page, counted from 0 row by row, to reuse partly used label sheets"),
+                )
+                .arg(
+                    Arg::new("skip-cells")
+                        .long("skip-cells")
+                        .value_parser(value_parser!(u32))
+                        .value_delimiter(',')
+                        .help("Cells of the first page to leave empty, e.g. 0,4,5"),
+                )
+                .arg(
+                    Arg::new("shelf")
+                        .long("shelf")
+                        .action(ArgAction::SetTrue)
+                        .help("Keep every barcode at its own size and pack them on shelves, for mixed templates"),
+                )
+                .arg(
+                    Arg::new("png")
+                        .long("png")
+                        .action(ArgAction::SetTrue)
+                        .help("Write PNG pages instead of one PDF"),
+                )
+                .arg(dpi)
+                .arg(workers)
+                .arg(abort)
+                .arg(verify)
+                .arg(min_grade),
+        )
+}
+
+fn generate(args: &ArgMatches) -> anyhow::Result<serde_json::Value> {
+    let mut config: BarcodeConfig = match args.get_one::<String>("config") {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => templates::get_template(string_arg(args, "template"))?.config,
+    };
+    if let Some(data) = args.get_one::<String>("data") {
+        config.data = data.clone();
+    }
+    let config: BarcodeConfigInternal = config.try_into()?;
+    let output = string_arg(args, "output");
+    let dpi = *args.get_one::<f32>("dpi").unwrap();
+
+    let generator = Generator::new();
+    let generated_barcode = generator.render_barcode(&config.data, &config, &output, dpi)?;
+    if args.get_flag("verify") {
+        Verifier::new().verify(&generated_barcode, config.format)?;
+    }
+    // Matrix codes have no linear grade and are let through, as in bulk runs
+    let grade = match min_grade(args).filter(|_| is_linear(config.format)) {
+        Some(minimum) => {
+            let grade = QualityGrader::new()
+                .grade(&generated_barcode, config.format)?
+                .grade;
+            if grade < minimum {
+                anyhow::bail!("Print quality grade {} is below {}", grade, minimum);
+            }
+            Some(grade)
+        }
+        None => None,
+    };
+    let is_svg = Path::new(&output)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if is_svg {
+        generator.write_svg(&generated_barcode, dpi)?;
+    } else {
+        generator.write_png(&generated_barcode, dpi)?;
+    }
+    Ok(serde_json::json!({
+        "file": output,
+        "value": generated_barcode.value,
+        "width_px": generated_barcode.buffer.width(),
+        "height_px": generated_barcode.buffer.height(),
+        "module_sizing": generated_barcode.module_sizing,
+        "grade": grade,
+    }))
+}
+
+fn bulk(args: &ArgMatches) -> anyhow::Result<(BulkGenerationResult, Option<PackingReport>)> {
+    let mut config = bulk_config(args);
+    config.file_name_pattern = string_arg(args, "file-name-pattern");
+    config.output_kind = if args.get_flag("svg") {
+        BulkOutputKind::IndividualSvg
+    } else {
+        BulkOutputKind::IndividualPng
+    };
+    Ok((FrontendInterface::new().generate_bulk(config, None)?, None))
+}
+
+fn export(args: &ArgMatches) -> anyhow::Result<(BulkGenerationResult, Option<PackingReport>)> {
+    let mut layout: Layout = match args.get_one::<String>("layout") {
+        Some(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+        None => Layout::new(),
+    };
+    if args.get_flag("png") {
+        layout.export_file = ExportFile::PNG;
+    }
+    if let Some(id) = args.get_one::<String>("label-stock") {
+        layout.label_stock = Some(label_stock::get_label_stock(id)?);
+    }
+    if args.get_flag("shelf") {
+        layout.packing = Packing::Shelf;
+    }
+    if let Some(cell) = args.get_one::<u32>("start-cell") {
+        layout.start_cell = *cell;
+    }
+    if let Some(cells) = args.get_many::<u32>("skip-cells") {
+        layout.skipped_cells = cells.copied().collect();
+    }
+    let mut config = bulk_config(args);
+    // Pages are laid out at the DPI the barcodes are rendered at
+    layout.dpi = config.dpi;
+    config.output_kind = BulkOutputKind::Paged(layout.clone());
+    let result = FrontendInterface::new().generate_bulk(config, None)?;
+    let report = ExportManager::new().packing_report(&layout, &result.barcodes)?;
+    Ok((result, Some(report)))
+}
+
+// Options shared by bulk and export
+fn bulk_config(args: &ArgMatches) -> BulkGeneratorConfig {
+    let mut config = BulkGeneratorConfig::new(
+        BulkSource::CsvPath(string_arg(args, "csv")),
+        string_arg(args, "template"),
+    );
+    config.output_dir = string_arg(args, "output-dir");
+    config.dpi = *args.get_one::<f32>("dpi").unwrap();
+    config.workers = *args.get_one::<usize>("workers").unwrap();
+    config.verify = args.get_flag("verify");
+    config.min_grade = min_grade(args);
+    if args.get_flag("abort") {
+        config.error_policy = ErrorPolicy::Abort;
+    }
+    config
+}
+
+fn string_arg(args: &ArgMatches, name: &str) -> String {
+    args.get_one::<String>(name).cloned().unwrap_or_default()
+}
+
+fn min_grade(args: &ArgMatches) -> Option<Grade> {
+    args.get_one::<String>("min-grade")
+        .map(|grade| match grade.as_str() {
+            "A" => Grade::A,
+            "B" => Grade::B,
+            "C" => Grade::C,
+            _ => Grade::D,
+        })
+}
+
+fn summarize(result: anyhow::Result<serde_json::Value>) -> (JsonResponse, u8) {
+    match result {
+        Ok(data) => (
+            JsonResponse {
+                success: true,
+                message: "Ok".to_string(),
+                data: Some(data),
+            },
+            EXIT_OK,
+        ),
+        Err(e) => (
+            JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            },
+            EXIT_FAILED,
+        ),
+    }
+}
+
+// Same summary the desktop app gets with its job-finished event, exports add their page utilisation.
+// Jobs which stopped part way still report the rows generated and failed until then
+fn summarize_bulk(
+    result: anyhow::Result<(BulkGenerationResult, Option<PackingReport>)>,
+) -> (JsonResponse, u8) {
+    match result {
+        Ok((result, report)) => {
+            let mut summary = result.summary();
+            if let Some(report) = report {
+                summary["pages"] = serde_json::to_value(report).unwrap();
+            }
+            let code = if result.failed.is_empty() {
+                EXIT_OK
+            } else {
+                EXIT_ROWS_FAILED
+            };
+            (summarize(Ok(summary)).0, code)
+        }
+        Err(e) => {
+            let partial = e
+                .downcast_ref::<BulkGenerationError>()
+                .map(|e| e.partial.summary());
+            let (mut response, code) = summarize(Err(e));
+            response.data = partial;
+            (response, code)
+        }
+    }
+}
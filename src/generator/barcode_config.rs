@@ -1,6 +1,8 @@
 use image::Rgb;
 use serde::{Deserialize, Serialize};
-use zxingcpp::BarcodeFormat;
+use zxingcpp::{BarcodeFormat, BarcodeFormats};
+
+use crate::generator::calculator::DimensionCalculator;
 
 // nOt serializable
 pub struct BarcodeConfigInternal {
@@ -10,6 +12,16 @@ pub struct BarcodeConfigInternal {
     pub quiet_zones: bool,
     pub dimensions: BarcodeDimensions,
     pub data: String,
+    pub colors: BarcodeColorsInternal,
+    pub transparent_background: bool,
+    pub human_readable: Option<HumanReadableConfig>,
+    pub x_dimension_mm: Option<f32>,
+    pub bar_width_reduction: Option<BarWidthReduction>,
+}
+
+pub struct BarcodeColorsInternal {
+    pub foreground: Rgb<u8>,
+    pub background: Rgb<u8>,
 }
 
 pub struct BarcodeTextStyleConfigInternal {
@@ -19,6 +31,8 @@ pub struct BarcodeTextStyleConfigInternal {
     pub text_position: TextPosition,
     pub font: String,
     pub margin: u32,
+    pub alignment: TextAlign,
+    pub line_spacing: f32,
 }
 
 // Serializable config for  tauri frontend
@@ -30,29 +44,139 @@ pub struct BarcodeConfig {
     pub quiet_zones: bool,
     pub dimensions: BarcodeDimensions,
     pub data: String,
+    // Templates saved before colours existed get black on white
+    #[serde(default = "BarcodeColors::new")]
+    pub colors: BarcodeColors,
+    // Background and quiet zones are left fully transparent in PNG output
+    #[serde(default)]
+    pub transparent_background: bool,
+    // Print the encoded data under linear codes the way the symbology specifies, None prints nothing
+    #[serde(default)]
+    pub human_readable: Option<HumanReadableConfig>,
+    // Size modules from the X-dimension instead of stretching the symbol to width_mm
+    #[serde(default)]
+    pub x_dimension: Option<XDimension>,
+    // Narrow every bar to make up for ink spreading on the printer
+    #[serde(default)]
+    pub bar_width_reduction: Option<BarWidthReduction>,
+}
+
+// Total width taken off each dark bar, half on either side
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BarWidthReduction {
+    pub value: f32,
+    pub unit: ReductionUnit,
+}
+
+impl BarWidthReduction {
+    pub fn to_px(&self, dpi: f32) -> f32 {
+        match self.unit {
+            ReductionUnit::Micrometers => self.value / 1000.0 / 25.4 * dpi,
+            ReductionUnit::Pixels => self.value,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ReductionUnit {
+    Micrometers,
+    Pixels,
+}
+
+// Width of the narrowest bar. The generator rounds it to whole pixels per module at the DPI,
+// so the printed size can differ slightly from the requested one
+#[derive(Clone, Serialize, Deserialize)]
+pub struct XDimension {
+    pub value: f32,
+    pub unit: LengthUnit,
+}
+
+impl XDimension {
+    pub fn to_mm(&self) -> f32 {
+        match self.unit {
+            LengthUnit::Mm => self.value,
+            LengthUnit::Mils => DimensionCalculator::new().mils_to_mm(self.value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum LengthUnit {
+    Mm,
+    // Thousandths of an inch
+    Mils,
+}
+
+// Human readable interpretation under the bars, drawn in the bar colour. Margin is the padding
+// in pixels above and below the digits
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HumanReadableConfig {
+    pub font: String,
+    pub text_size: u32,
+    pub margin: u32,
 }
 
-impl From<BarcodeConfig> for BarcodeConfigInternal {
-    fn from(config: BarcodeConfig) -> Self {
-        BarcodeConfigInternal {
-            format: config.format.into(),
+impl HumanReadableConfig {
+    pub fn new() -> Self {
+        Self {
+            font: "DejaVuSans".to_string(),
+            text_size: 20,
+            margin: 2,
+        }
+    }
+}
+
+// Bars use the foreground, quiet zones and the space around texts use the background
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BarcodeColors {
+    pub foreground: RgbWrapper,
+    pub background: RgbWrapper,
+}
+
+impl BarcodeColors {
+    pub fn new() -> Self {
+        Self {
+            foreground: Rgb([0, 0, 0]).into(),
+            background: Rgb([255, 255, 255]).into(),
+        }
+    }
+}
+
+impl TryFrom<BarcodeConfig> for BarcodeConfigInternal {
+    type Error = BarcodeFormatError;
+
+    fn try_from(config: BarcodeConfig) -> Result<Self, Self::Error> {
+        Ok(BarcodeConfigInternal {
+            format: config.format.try_into()?,
             texts: config
                 .texts
                 .into_iter()
                 .map(|text| BarcodeTextStyleConfigInternal {
+                    alignment: text
+                        .alignment
+                        .unwrap_or_else(|| TextAlign::from_position(&text.text_position)),
                     text: text.text,
                     text_color: text.text_color.into(),
                     text_size: text.text_size,
                     text_position: text.text_position,
                     font: text.font,
                     margin: text.margin,
+                    line_spacing: text.line_spacing,
                 })
                 .collect(),
             scale: config.scale,
             quiet_zones: config.quiet_zones,
             dimensions: config.dimensions,
             data: config.data,
-        }
+            colors: BarcodeColorsInternal {
+                foreground: config.colors.foreground.into(),
+                background: config.colors.background.into(),
+            },
+            transparent_background: config.transparent_background,
+            human_readable: config.human_readable,
+            x_dimension_mm: config.x_dimension.map(|x_dimension| x_dimension.to_mm()),
+            bar_width_reduction: config.bar_width_reduction,
+        })
     }
 }
 
@@ -62,24 +186,77 @@ pub struct BarcodeFormatWrapper {
     pub format: String,
 }
 
-impl From<BarcodeFormatWrapper> for BarcodeFormat {
-    fn from(wrapper: BarcodeFormatWrapper) -> Self {
-        match wrapper.format.as_str() {
-            "Code128" => BarcodeFormat::Code128,
-            "Code39" => BarcodeFormat::Code39,
-            "QRCode" => BarcodeFormat::QRCode,
-            "EAN13" => BarcodeFormat::EAN13,
-            "EAN8" => BarcodeFormat::EAN8,
-            "UPCA" => BarcodeFormat::UPCA,
-            "UPCE" => BarcodeFormat::UPCE,
-            "DataMatrix" => BarcodeFormat::DataMatrix,
-            "PDF417" => BarcodeFormat::PDF417,
-            "Aztec" => BarcodeFormat::Aztec,
-            _ => BarcodeFormat::Code128, // Default fallback
+// Every format zxing-cpp can create, DXFilmEdge and the format groups are read only
+pub const WRITABLE_FORMATS: [BarcodeFormat; 19] = [
+    BarcodeFormat::Aztec,
+    BarcodeFormat::Codabar,
+    BarcodeFormat::Code39,
+    BarcodeFormat::Code93,
+    BarcodeFormat::Code128,
+    BarcodeFormat::DataBar,
+    BarcodeFormat::DataBarExpanded,
+    BarcodeFormat::DataBarLimited,
+    BarcodeFormat::DataMatrix,
+    BarcodeFormat::EAN8,
+    BarcodeFormat::EAN13,
+    BarcodeFormat::ITF,
+    BarcodeFormat::MaxiCode,
+    BarcodeFormat::MicroQRCode,
+    BarcodeFormat::PDF417,
+    BarcodeFormat::QRCode,
+    BarcodeFormat::RMQRCode,
+    BarcodeFormat::UPCA,
+    BarcodeFormat::UPCE,
+];
+
+// Formats drawn as one row of bars, the rest are matrix codes
+pub fn is_linear(format: BarcodeFormat) -> bool {
+    BarcodeFormats::from(BarcodeFormat::LinearCodes).contains(format)
+}
+
+const READ_ONLY_FORMATS: [BarcodeFormat; 5] = [
+    BarcodeFormat::DXFilmEdge,
+    BarcodeFormat::LinearCodes,
+    BarcodeFormat::MatrixCodes,
+    BarcodeFormat::Any,
+    BarcodeFormat::None,
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BarcodeFormatError {
+    Unknown(String),
+    NotWritable(String),
+}
+
+impl std::fmt::Display for BarcodeFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BarcodeFormatError::Unknown(format) => write!(f, "Unknown barcode format: {}", format),
+            BarcodeFormatError::NotWritable(format) => {
+                write!(f, "Barcode format {} can not be generated", format)
+            }
         }
     }
 }
 
+impl std::error::Error for BarcodeFormatError {}
+
+impl TryFrom<BarcodeFormatWrapper> for BarcodeFormat {
+    type Error = BarcodeFormatError;
+
+    fn try_from(wrapper: BarcodeFormatWrapper) -> Result<Self, Self::Error> {
+        let name_matches =
+            |format: &&BarcodeFormat| BarcodeFormatWrapper::from(**format).format == wrapper.format;
+        if let Some(format) = WRITABLE_FORMATS.iter().find(name_matches) {
+            return Ok(*format);
+        }
+        if READ_ONLY_FORMATS.iter().any(|format| name_matches(&format)) {
+            return Err(BarcodeFormatError::NotWritable(wrapper.format));
+        }
+        Err(BarcodeFormatError::Unknown(wrapper.format))
+    }
+}
+
 impl From<BarcodeFormat> for BarcodeFormatWrapper {
     fn from(format: BarcodeFormat) -> Self {
         let format_str = match format {
@@ -160,6 +337,8 @@ impl BarcodeDimensions {
     }
 }
 
+// Text may contain newlines and is wrapped to the barcode width. Margin is the padding
+// in pixels above and below the text block
 #[derive(Clone, Serialize, Deserialize)]
 pub struct BarcodeTextStyleConfig {
     pub text: String,
@@ -168,6 +347,16 @@ pub struct BarcodeTextStyleConfig {
     pub text_position: TextPosition,
     pub font: String,
     pub margin: u32,
+    // When missing the position decides, centered positions center and the others align left
+    #[serde(default)]
+    pub alignment: Option<TextAlign>,
+    // Distance between baselines as a multiple of the text size
+    #[serde(default = "default_line_spacing")]
+    pub line_spacing: f32,
+}
+
+fn default_line_spacing() -> f32 {
+    1.0
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -202,6 +391,22 @@ pub enum TextPosition {
     LowerCenter,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    pub fn from_position(position: &TextPosition) -> Self {
+        match position {
+            TextPosition::UpperCenter | TextPosition::LowerCenter => TextAlign::Center,
+            _ => TextAlign::Left,
+        }
+    }
+}
+
 pub struct BarcodeConfigBuilder {
     config: BarcodeConfig,
 }
@@ -213,6 +418,11 @@ impl BarcodeConfigBuilder {
         }
     }
 
+    // Start from an existing config, e.g. the one saved in a template
+    pub fn from_config(config: BarcodeConfig) -> Self {
+        Self { config }
+    }
+
     fn default_config() -> BarcodeConfig {
         BarcodeConfig {
             format: BarcodeFormatWrapper {
@@ -223,6 +433,11 @@ impl BarcodeConfigBuilder {
             quiet_zones: true,
             dimensions: BarcodeDimensions::new(),
             data: String::new(),
+            colors: BarcodeColors::new(),
+            transparent_background: false,
+            human_readable: None,
+            x_dimension: None,
+            bar_width_reduction: None,
         }
     }
 
@@ -250,10 +465,87 @@ impl BarcodeConfigBuilder {
             text_position,
             font: "DejaVuSans".to_string(),
             margin: 5,
+            alignment: None,
+            line_spacing: default_line_spacing(),
         });
         self
     }
 
+    // Replace the text at the position, or add one styled like the first existing text
+    pub fn set_position_text(
+        &mut self,
+        text: &str,
+        text_position: TextPosition,
+        text_size: Option<u32>,
+    ) -> &mut Self {
+        let existing = self
+            .config
+            .texts
+            .iter_mut()
+            .find(|t| t.text_position == text_position);
+        match existing {
+            Some(existing) => {
+                existing.text = text.to_string();
+                if let Some(text_size) = text_size {
+                    existing.text_size = text_size;
+                }
+            }
+            None => {
+                let mut style = match self.config.texts.first() {
+                    Some(first) => first.clone(),
+                    None => BarcodeTextStyleConfig {
+                        text: String::new(),
+                        text_color: Rgb([0, 0, 0]).into(),
+                        text_size: 20,
+                        text_position: TextPosition::None,
+                        font: "DejaVuSans".to_string(),
+                        margin: 5,
+                        alignment: None,
+                        line_spacing: default_line_spacing(),
+                    },
+                };
+                style.text = text.to_string();
+                style.text_position = text_position;
+                if let Some(text_size) = text_size {
+                    style.text_size = text_size;
+                }
+                self.config.texts.push(style);
+            }
+        }
+        self
+    }
+
+    pub fn set_colors(&mut self, foreground: Rgb<u8>, background: Rgb<u8>) -> &mut Self {
+        self.config.colors = BarcodeColors {
+            foreground: foreground.into(),
+            background: background.into(),
+        };
+        self
+    }
+
+    pub fn set_transparent_background(&mut self, transparent: bool) -> &mut Self {
+        self.config.transparent_background = transparent;
+        self
+    }
+
+    pub fn set_human_readable(&mut self, human_readable: Option<HumanReadableConfig>) -> &mut Self {
+        self.config.human_readable = human_readable;
+        self
+    }
+
+    pub fn set_x_dimension(&mut self, x_dimension: Option<XDimension>) -> &mut Self {
+        self.config.x_dimension = x_dimension;
+        self
+    }
+
+    pub fn set_bar_width_reduction(
+        &mut self,
+        bar_width_reduction: Option<BarWidthReduction>,
+    ) -> &mut Self {
+        self.config.bar_width_reduction = bar_width_reduction;
+        self
+    }
+
     pub fn resize_height_percentage(&mut self, percentage: f32) -> &mut Self {
         self.config.dimensions = self.config.dimensions.resize_height_percentage(percentage);
         self
@@ -273,3 +565,36 @@ impl BarcodeConfigBuilder {
         self.config.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writable_formats_round_trip() {
+        for format in WRITABLE_FORMATS {
+            let wrapper = BarcodeFormatWrapper::from(format);
+            assert_eq!(BarcodeFormat::try_from(wrapper), Ok(format));
+        }
+    }
+
+    #[test]
+    fn test_unknown_format_is_error() {
+        let wrapper = BarcodeFormatWrapper {
+            format: "Code-128".to_string(),
+        };
+        assert_eq!(
+            BarcodeFormat::try_from(wrapper),
+            Err(BarcodeFormatError::Unknown("Code-128".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_read_only_format_is_error() {
+        let wrapper = BarcodeFormatWrapper::from(BarcodeFormat::DXFilmEdge);
+        assert_eq!(
+            BarcodeFormat::try_from(wrapper),
+            Err(BarcodeFormatError::NotWritable("DXFilmEdge".to_string()))
+        );
+    }
+}
@@ -1,79 +1,569 @@
-use image::Rgb;
-use tauri::utils::config;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rayon::prelude::*;
+use serde::Serialize;
 use zxingcpp::BarcodeFormat;
 
+use crate::generator::barcode_config::is_linear;
+use crate::generator::barcode_config::BarcodeConfig;
 use crate::generator::barcode_config::BarcodeConfigBuilder;
+use crate::generator::barcode_config::BarcodeConfigInternal;
 use crate::generator::barcode_config::TextPosition;
+use crate::generator::bulk_generator_config::{
+    BulkGeneratorConfig, BulkOutputKind, BulkSource, ErrorPolicy,
+};
 use crate::generator::generator::GeneratedBarcode;
 use crate::generator::generator::Generator;
 // Generate barcodes in bulk and export to file
-use crate::generator::importer::BarcodeImportRowCSV;
+use crate::generator::importer::{BarcodeImportRowCSV, Importer};
+use crate::generator::job::Job;
+use crate::generator::layout::ExportManager;
+use crate::generator::quality::QualityGrader;
+use crate::generator::templates::{self, Template};
+use crate::generator::validation::{ValidationError, ValidationReason, Validator};
+use crate::generator::verification::Verifier;
 
 pub struct BulkGenerator {
-    output_dir: String,
+    config: BulkGeneratorConfig,
+    job: Job,
+}
+
+// Row that was skipped, row is the zero based index in the imported rows
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedRow {
+    pub row: usize,
+    pub value: String,
+    pub errors: Vec<ValidationError>,
 }
 
+// Row that was generated, file_path is the written file and None for paged output
+#[derive(Debug, Clone, Serialize)]
+pub struct GeneratedRow {
+    pub row: usize,
+    pub value: String,
+    pub file_path: Option<String>,
+}
+
+pub struct BulkGenerationResult {
+    pub generated: Vec<GeneratedRow>,
+    // Rendered barcodes for placing on pages, only kept for paged output.
+    // Individual files are written as they are generated and their buffers dropped
+    pub barcodes: Vec<GeneratedBarcode>,
+    pub failed: Vec<FailedRow>,
+}
+
+impl BulkGenerationResult {
+    // Counts and failed rows as reported to the app and the CLI
+    pub fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "generated": self.generated.len(),
+            "failed": self.failed,
+        })
+    }
+}
+
+// Job stopped part way, by ErrorPolicy::Abort or a failed page export.
+// Partial holds the rows generated and failed until then, files of generated rows may already be written
+pub struct BulkGenerationError {
+    pub error: anyhow::Error,
+    pub partial: BulkGenerationResult,
+}
+
+impl std::fmt::Display for BulkGenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::fmt::Debug for BulkGenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BulkGenerationError")
+            .field("error", &self.error)
+            .field("generated", &self.partial.generated)
+            .field("failed", &self.partial.failed)
+            .finish()
+    }
+}
+
+impl std::error::Error for BulkGenerationError {}
+
 impl BulkGenerator {
-    pub fn new() -> Self {
+    pub fn new(config: BulkGeneratorConfig) -> Self {
         Self {
-            output_dir: "output".to_string(),
+            config,
+            job: Job::new(),
         }
     }
 
-    pub fn generate_barcodes(
+    // Report progress and check for cancellation through the given job
+    pub fn with_job(self, job: Job) -> Self {
+        Self { job, ..self }
+    }
+
+    // Run the whole job: read the source, generate with the template and write the outputs
+    pub fn run(&self) -> anyhow::Result<BulkGenerationResult> {
+        let importer = Importer::new();
+        let barcodes = match &self.config.source {
+            BulkSource::CsvBytes(bytes) => importer.import_from_csv_bytes(bytes.clone())?,
+            BulkSource::CsvPath(path) => importer.import_from_csv(path)?,
+            BulkSource::Rows(rows) => rows.clone(),
+        };
+        let template = templates::get_template(self.config.template_id.clone())?;
+        let result = self.generate_barcodes(&template, barcodes)?;
+
+        if let BulkOutputKind::Paged(layout) = &self.config.output_kind {
+            // Sheets hold many values so they are named after the template
+            let output_path = Path::new(&self.config.output_dir).join(&template.id);
+            // Pages are laid out at the DPI the barcodes were rendered at
+            let mut layout = layout.clone();
+            layout.dpi = self.config.dpi;
+            let export = ExportManager::new()
+                .with_job(self.job.clone())
+                .generate_export_pages(layout, &result.barcodes, &output_path.to_string_lossy());
+            if let Err(error) = export {
+                return Err(BulkGenerationError {
+                    error,
+                    partial: result,
+                }
+                .into());
+            }
+        }
+        Ok(result)
+    }
+
+    // Template config with the values given in the row laid over it
+    pub fn row_config(&self, template: &Template, barcode: &BarcodeImportRowCSV) -> BarcodeConfig {
+        let mut config_builder = BarcodeConfigBuilder::from_config(template.config.clone());
+        if !barcode.upper_center_text.is_empty() {
+            config_builder.set_position_text(
+                barcode.upper_center_text.as_str(),
+                TextPosition::UpperCenter,
+                barcode.font_size,
+            );
+        }
+        if !barcode.lower_center_text.is_empty() {
+            config_builder.set_position_text(
+                barcode.lower_center_text.as_str(),
+                TextPosition::LowerCenter,
+                barcode.font_size,
+            );
+        }
+        if let Some(scale) = barcode.scale {
+            config_builder.set_scale(scale);
+        }
+        if let Some(height_percentage) = barcode.height_percentage {
+            config_builder.resize_height_percentage(height_percentage);
+        }
+        if let Some(width_percentage) = barcode.width_percentage {
+            config_builder.resize_width_percentage(width_percentage);
+        }
+        config_builder.set_data(barcode.value.clone());
+        config_builder.build()
+    }
+
+    // Validate every row up front, so bad rows are reported instead of aborting the whole run
+    pub fn validate_rows(
         &self,
+        format: BarcodeFormat,
         barcodes: Vec<BarcodeImportRowCSV>,
-    ) -> anyhow::Result<Vec<GeneratedBarcode>> {
-        self.generate_barcodes_with_dpi(barcodes, 300.0)
+    ) -> (Vec<(usize, BarcodeImportRowCSV)>, Vec<FailedRow>) {
+        let validator = Validator::new();
+        let mut valid_rows = Vec::new();
+        let mut failed_rows = Vec::new();
+        for (row, mut barcode) in barcodes.into_iter().enumerate() {
+            match validator.validate(format, "value", &barcode.value) {
+                Ok(value) => {
+                    barcode.value = value;
+                    valid_rows.push((row, barcode));
+                }
+                Err(e) => failed_rows.push(FailedRow {
+                    row,
+                    value: barcode.value,
+                    errors: e.errors,
+                }),
+            }
+        }
+        (valid_rows, failed_rows)
     }
 
-    pub fn generate_barcodes_with_dpi(
+    // Generate every valid row, individual outputs are written to the output directory as they are generated
+    pub fn generate_barcodes(
         &self,
+        template: &Template,
         barcodes: Vec<BarcodeImportRowCSV>,
-        dpi: f32,
-    ) -> anyhow::Result<Vec<GeneratedBarcode>> {
+    ) -> anyhow::Result<BulkGenerationResult> {
+        self.config.validate()?;
         let generator = Generator::new();
+        let format: BarcodeFormat = template.config.format.clone().try_into()?;
+        fs::create_dir_all(&self.config.output_dir)?;
 
+        self.job.set_rows_total(barcodes.len());
+        let (valid_rows, mut failed_rows) = self.validate_rows(format, barcodes);
+        let valid_rows = self.unique_file_names(valid_rows, &mut failed_rows);
+        failed_rows.sort_by_key(|failed| failed.row);
+        self.job.rows_failed(failed_rows.len());
+        if self.config.error_policy == ErrorPolicy::Abort && !failed_rows.is_empty() {
+            return Err(aborted(BulkGenerationResult {
+                generated: Vec::new(),
+                barcodes: Vec::new(),
+                failed: failed_rows,
+            }));
+        }
+
+        // Rows are rendered on a worker pool, collect keeps them in row order
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.config.workers)
+            .build()?;
+        let stop = AtomicBool::new(false);
+        let outcomes = pool.install(|| {
+            valid_rows
+                .into_par_iter()
+                .map(|(row, barcode)| {
+                    self.job.check_cancelled()?;
+                    // With Abort, rows which did not start yet are left out after the first failure
+                    if stop.load(Ordering::Relaxed) {
+                        return anyhow::Ok(None);
+                    }
+                    let outcome = self.generate_row(&generator, template, row, barcode);
+                    match &outcome {
+                        Ok(_) => self.job.row_done(),
+                        Err(_) => {
+                            self.job.rows_failed(1);
+                            if self.config.error_policy == ErrorPolicy::Abort {
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                    }
+                    anyhow::Ok(Some(outcome))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+
+        let mut generated_rows: Vec<GeneratedRow> = Vec::new();
         let mut generated_barcodes: Vec<GeneratedBarcode> = Vec::new();
+        for outcome in outcomes.into_iter().flatten() {
+            match outcome {
+                Ok((generated_row, generated_barcode)) => {
+                    generated_rows.push(generated_row);
+                    generated_barcodes.extend(generated_barcode);
+                }
+                Err(failed) => failed_rows.push(failed),
+            }
+        }
+        failed_rows.sort_by_key(|failed| failed.row);
+        let result = BulkGenerationResult {
+            generated: generated_rows,
+            barcodes: generated_barcodes,
+            failed: failed_rows,
+        };
+        if self.config.error_policy == ErrorPolicy::Abort && !result.failed.is_empty() {
+            return Err(aborted(result));
+        }
+        Ok(result)
+    }
 
-        for barcode in barcodes {
-            let mut config_builder = BarcodeConfigBuilder::new();
-            config_builder.set_format(BarcodeFormat::Code39);
-            // Add upper text if not empty
-            if !barcode.upper_center_text.is_empty() {
-                config_builder.add_text(
-                    barcode.upper_center_text.as_str(),
-                    Rgb([255, 0, 0]),
-                    barcode.font_size,
-                    TextPosition::UpperCenter,
-                );
+    // Rows whose file name was already taken by an earlier row would overwrite its file, so they fail.
+    // Names are compared case insensitively as not every file system tells them apart
+    fn unique_file_names(
+        &self,
+        rows: Vec<(usize, BarcodeImportRowCSV)>,
+        failed_rows: &mut Vec<FailedRow>,
+    ) -> Vec<(usize, BarcodeImportRowCSV)> {
+        if let BulkOutputKind::Paged(_) = self.config.output_kind {
+            return rows;
+        }
+        let mut taken: HashMap<String, usize> = HashMap::new();
+        let mut unique_rows = Vec::with_capacity(rows.len());
+        for (row, barcode) in rows {
+            let file_name = self.config.file_name(row, &barcode.value);
+            match taken.get(&file_name.to_lowercase()) {
+                Some(&first_row) => failed_rows.push(FailedRow {
+                    row,
+                    value: barcode.value,
+                    errors: vec![ValidationError {
+                        field: "value".to_string(),
+                        position: None,
+                        reason: ValidationReason::DuplicateFileName {
+                            file_name,
+                            row: first_row,
+                        },
+                    }],
+                }),
+                None => {
+                    taken.insert(file_name.to_lowercase(), row);
+                    unique_rows.push((row, barcode));
+                }
             }
+        }
+        unique_rows
+    }
 
-            // Add lower text if not empty
-            if !barcode.lower_center_text.is_empty() {
-                config_builder.add_text(
-                    barcode.lower_center_text.as_str(),
-                    Rgb([255, 0, 0]),
-                    barcode.font_size,
-                    TextPosition::LowerCenter,
-                );
+    // Generate one validated row, verify and grade it when asked and write it when the output kind has a file per barcode.
+    // The rendered barcode is only returned for paged output
+    fn generate_row(
+        &self,
+        generator: &Generator,
+        template: &Template,
+        row: usize,
+        barcode: BarcodeImportRowCSV,
+    ) -> Result<(GeneratedRow, Option<GeneratedBarcode>), FailedRow> {
+        let failed = |reason: ValidationReason| FailedRow {
+            row,
+            value: barcode.value.clone(),
+            errors: vec![ValidationError {
+                field: "value".to_string(),
+                position: None,
+                reason,
+            }],
+        };
+        let encoder_rejected = |e: anyhow::Error| {
+            failed(ValidationReason::EncoderRejected {
+                message: e.to_string(),
+            })
+        };
+
+        let file_path = Path::new(&self.config.output_dir)
+            .join(self.config.file_name(row, &barcode.value))
+            .to_string_lossy()
+            .to_string();
+        let file_path = match self.config.output_kind {
+            BulkOutputKind::IndividualPng => format!("{}.png", file_path),
+            BulkOutputKind::IndividualSvg => format!("{}.svg", file_path),
+            BulkOutputKind::Paged(_) => file_path,
+        };
+        let config = BarcodeConfigInternal::try_from(self.row_config(template, &barcode))
+            .map_err(|e| encoder_rejected(e.into()))?;
+        let dpi = self.config.dpi;
+        let generated_barcode = generator
+            .render_barcode(&barcode.value, &config, &file_path, dpi)
+            .map_err(encoder_rejected)?;
+
+        // Barcodes failing verification or grading are never written
+        if self.config.verify {
+            Verifier::new()
+                .verify(&generated_barcode, config.format)
+                .map_err(|failure| failed(ValidationReason::VerificationFailed { failure }))?;
+        }
+        // Matrix codes have no linear grade and are let through
+        if let Some(minimum) = self.config.min_grade.filter(|_| is_linear(config.format)) {
+            let report = QualityGrader::new()
+                .grade(&generated_barcode, config.format)
+                .map_err(|e| {
+                    failed(ValidationReason::GradingFailed {
+                        message: e.to_string(),
+                    })
+                })?;
+            if report.grade < minimum {
+                return Err(failed(ValidationReason::GradeTooLow {
+                    grade: report.grade,
+                    minimum,
+                }));
             }
+        }
+        match self.config.output_kind {
+            BulkOutputKind::IndividualPng => generator.write_png(&generated_barcode, dpi),
+            BulkOutputKind::IndividualSvg => generator.write_svg(&generated_barcode, dpi),
+            BulkOutputKind::Paged(_) => Ok(()),
+        }
+        .map_err(encoder_rejected)?;
+        let paged = matches!(self.config.output_kind, BulkOutputKind::Paged(_));
+        let generated_row = GeneratedRow {
+            row,
+            value: generated_barcode.value.clone(),
+            file_path: (!paged).then(|| generated_barcode.file_path.clone()),
+        };
+        Ok((generated_row, paged.then_some(generated_barcode)))
+    }
+}
+
+// Abort error naming the first failed row, partial must have at least one
+fn aborted(partial: BulkGenerationResult) -> anyhow::Error {
+    let error = row_error(&partial.failed[0]);
+    BulkGenerationError { error, partial }.into()
+}
+
+fn row_error(failed: &FailedRow) -> anyhow::Error {
+    let messages: Vec<String> = failed.errors.iter().map(|e| e.to_string()).collect();
+    anyhow::anyhow!(
+        "Row {} ({}) failed: {}",
+        failed.row + 1,
+        failed.value,
+        messages.join("; ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::layout::Layout;
+
+    fn row(value: &str) -> BarcodeImportRowCSV {
+        BarcodeImportRowCSV {
+            value: value.to_string(),
+            upper_center_text: String::new(),
+            lower_center_text: String::new(),
+            scale: None,
+            height_percentage: None,
+            width_percentage: None,
+            font_size: None,
+        }
+    }
+
+    fn template() -> Template {
+        let config = BarcodeConfigBuilder::new()
+            .set_format(BarcodeFormat::Code128)
+            .build();
+        Template::new(config, "Test".to_string(), String::new())
+    }
+
+    // Empty directory under the system temp dir for the files of one test
+    fn output_dir(name: &str) -> String {
+        let dir =
+            std::env::temp_dir().join(format!("bulk_generator_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_individual_files_drop_buffers() {
+        let template = template();
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_dir = output_dir("individual");
+        let result = BulkGenerator::new(config)
+            .generate_barcodes(&template, vec![row("A1"), row("B2")])
+            .unwrap();
+        assert!(result.barcodes.is_empty());
+        assert_eq!(result.generated.len(), 2);
+        for generated in &result.generated {
+            assert!(Path::new(generated.file_path.as_ref().unwrap()).exists());
+        }
+
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_dir = output_dir("paged");
+        config.output_kind = BulkOutputKind::Paged(Layout::new());
+        let result = BulkGenerator::new(config)
+            .generate_barcodes(&template, vec![row("A1"), row("B2")])
+            .unwrap();
+        assert_eq!(result.barcodes.len(), 2);
+        assert!(result
+            .generated
+            .iter()
+            .all(|generated| generated.file_path.is_none()));
+    }
+
+    #[test]
+    fn test_duplicate_file_names_fail() {
+        let template = template();
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_dir = output_dir("duplicates");
+        let result = BulkGenerator::new(config)
+            .generate_barcodes(&template, vec![row("A1"), row("B2"), row("A1"), row("a1")])
+            .unwrap();
+        let generated: Vec<usize> = result.generated.iter().map(|g| g.row).collect();
+        assert_eq!(generated, vec![0, 1]);
+        let failed: Vec<usize> = result.failed.iter().map(|f| f.row).collect();
+        assert_eq!(failed, vec![2, 3]);
+        assert_eq!(
+            result.failed[0].errors[0].reason,
+            ValidationReason::DuplicateFileName {
+                file_name: "A1".to_string(),
+                row: 0
+            }
+        );
+
+        // The row number keeps the names apart
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_dir = output_dir("duplicates_row");
+        config.file_name_pattern = "{row}_{value}".to_string();
+        let result = BulkGenerator::new(config)
+            .generate_barcodes(&template, vec![row("A1"), row("A1")])
+            .unwrap();
+        assert_eq!(result.generated.len(), 2);
+        assert!(result.failed.is_empty());
+    }
+
+    #[test]
+    fn test_dpi_must_be_positive() {
+        let template = template();
+        for dpi in [0.0, -300.0, f32::NAN] {
+            let mut config =
+                BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+            config.output_dir = output_dir("dpi");
+            config.dpi = dpi;
+            assert!(BulkGenerator::new(config)
+                .generate_barcodes(&template, vec![row("A1")])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_row_values_override_template() {
+        let mut builder = BarcodeConfigBuilder::new();
+        builder
+            .set_format(BarcodeFormat::Code128)
+            .set_scale(5)
+            .set_colors(image::Rgb([200, 0, 0]), image::Rgb([255, 255, 255]))
+            .add_text("Upper", image::Rgb([0, 0, 200]), 24, TextPosition::Upper)
+            .resize_width_percentage(80.0);
+        let template = Template::new(builder.build(), "Test".to_string(), String::new());
+        let generator = BulkGenerator::new(BulkGeneratorConfig::new(
+            BulkSource::Rows(vec![]),
+            template.id.clone(),
+        ));
+
+        let mut barcode = row("ROW1");
+        barcode.lower_center_text = "Lower".to_string();
+        barcode.font_size = Some(30);
+        barcode.scale = Some(8);
+        barcode.height_percentage = Some(50.0);
+        let config = generator.row_config(&template, &barcode);
+        assert_eq!(config.data, "ROW1");
+        assert_eq!(config.scale, 8);
+        assert_eq!(config.dimensions.height_percentage, 50.0);
+        // Not set in the row, so the template values stay
+        assert_eq!(config.dimensions.width_percentage, 80.0);
+        assert_eq!(
+            serde_json::to_value(&config.colors).unwrap(),
+            serde_json::to_value(&template.config.colors).unwrap()
+        );
+        assert_eq!(config.texts.len(), 2);
+        assert_eq!(config.texts[0].text, "Upper");
+        assert_eq!(config.texts[0].text_size, 24);
+        // The added text is styled like the template text
+        let lower = &config.texts[1];
+        assert!(lower.text_position == TextPosition::LowerCenter);
+        assert_eq!(lower.text, "Lower");
+        assert_eq!(lower.text_size, 30);
+        assert_eq!(
+            serde_json::to_value(&lower.text_color).unwrap(),
+            serde_json::to_value(&config.texts[0].text_color).unwrap()
+        );
+
+        // A row setting nothing but the value keeps the whole template
+        let config = generator.row_config(&template, &row("ROW2"));
+        assert_eq!(config.data, "ROW2");
+        assert_eq!(config.scale, 5);
+        assert_eq!(config.dimensions.height_percentage, 100.0);
+        assert_eq!(config.texts.len(), 1);
+    }
 
-            config_builder.set_scale(barcode.scale);
-            config_builder.resize_height_percentage(barcode.height_percentage);
-            config_builder.resize_width_percentage(barcode.width_percentage);
-            config_builder.set_data(barcode.value.clone());
-            let config = config_builder.build();
-            let internal_config: crate::generator::barcode_config::BarcodeConfigInternal =
-                config.into();
-            let generated_barcode = generator.generate_barcode_png_with_dpi(
-                barcode.value.as_str(),
-                internal_config,
-                &format!("{}/{}.png", self.output_dir, barcode.value),
-                dpi,
-            )?;
-            generated_barcodes.push(generated_barcode);
-        }
-        Ok(generated_barcodes)
+    #[test]
+    fn test_abort_keeps_failed_rows() {
+        let template = template();
+        let mut config = BulkGeneratorConfig::new(BulkSource::Rows(vec![]), template.id.clone());
+        config.output_dir = output_dir("abort");
+        config.error_policy = ErrorPolicy::Abort;
+        let error = BulkGenerator::new(config)
+            .generate_barcodes(&template, vec![row("A1"), row(""), row("B2")])
+            .err()
+            .unwrap();
+        let error = error.downcast_ref::<BulkGenerationError>().unwrap();
+        assert!(error.to_string().starts_with("Row 2 () failed"));
+        assert!(error.partial.generated.is_empty());
+        let failed: Vec<usize> = error.partial.failed.iter().map(|f| f.row).collect();
+        assert_eq!(failed, vec![1]);
     }
 }
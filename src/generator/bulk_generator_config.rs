@@ -1 +1,90 @@
-pub struct BulkGeneratorConfig {}
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{importer::BarcodeImportRowCSV, layout::Layout, quality::Grade};
+
+// Where the rows of a bulk job come from
+#[derive(Serialize, Deserialize)]
+pub enum BulkSource {
+    CsvBytes(Vec<u8>),
+    CsvPath(String),
+    // Rows which are already imported, e.g. kept in the app state
+    Rows(Vec<BarcodeImportRowCSV>),
+}
+
+// Individual outputs write one file per barcode, paged output places them on sheets
+// and the layout export_file decides between PNG pages and one PDF
+#[derive(Serialize, Deserialize)]
+pub enum BulkOutputKind {
+    IndividualPng,
+    IndividualSvg,
+    Paged(Layout),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    // Report failed rows and keep going
+    Skip,
+    // Stop the job at the first failed row
+    Abort,
+}
+
+// Describes one bulk generation job
+#[derive(Serialize, Deserialize)]
+pub struct BulkGeneratorConfig {
+    pub source: BulkSource,
+    pub template_id: String,
+    pub output_dir: String,
+    // {value}, {row} and {template} are replaced per barcode, the extension is added from the output kind.
+    // Paged output is named after the template instead
+    pub file_name_pattern: String,
+    pub dpi: f32,
+    pub output_kind: BulkOutputKind,
+    pub error_policy: ErrorPolicy,
+    // Rows rendered in parallel, 0 uses one worker per CPU core
+    pub workers: usize,
+    // Read every barcode back before writing it, rows which don't decode to their value fail
+    pub verify: bool,
+    // Rows of linear formats graded below this fail, None skips grading
+    pub min_grade: Option<Grade>,
+}
+
+impl BulkGeneratorConfig {
+    pub fn new(source: BulkSource, template_id: String) -> Self {
+        Self {
+            source,
+            template_id,
+            output_dir: "output".to_string(),
+            file_name_pattern: "{value}".to_string(),
+            dpi: 300.0,
+            output_kind: BulkOutputKind::IndividualPng,
+            error_policy: ErrorPolicy::Skip,
+            workers: 0,
+            verify: false,
+            min_grade: None,
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !self.dpi.is_finite() || self.dpi <= 0.0 {
+            anyhow::bail!("DPI must be larger than 0, got {}", self.dpi);
+        }
+        Ok(())
+    }
+
+    // Row is the zero based index of the imported row, named files count from 1 like the CSV data lines
+    pub fn file_name(&self, row: usize, value: &str) -> String {
+        let name = self
+            .file_name_pattern
+            .replace("{value}", value)
+            .replace("{row}", &(row + 1).to_string())
+            .replace("{template}", &self.template_id);
+        // Values may contain characters which are not allowed in file names
+        name.chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect()
+    }
+}
@@ -28,6 +28,16 @@ impl DimensionCalculator {
         px as f32 / dpi
     }
 
+    /// Converts mils (thousandths of an inch) to millimeters
+    pub fn mils_to_mm(&self, mils: f32) -> f32 {
+        mils / 1000.0 * 25.4
+    }
+
+    /// Whole pixels per module closest to the X-dimension at the given DPI, at least one
+    pub fn pixels_per_module(&self, x_dimension_mm: f32, dpi: f32) -> u32 {
+        self.mm_to_px(x_dimension_mm, dpi).max(1)
+    }
+
     /// Gets dimensions in pixels for A4 paper at the given DPI
     pub fn get_a4_dimensions_px(&self, dpi: f32) -> (u32, u32) {
         // A4 dimensions in mm: 210 x 297
@@ -54,6 +64,14 @@ mod tests {
         assert!((calc.px_to_mm(300, 300.0) - 25.4).abs() < 0.001); // 1 inch
     }
 
+    #[test]
+    fn test_pixels_per_module() {
+        let calc = DimensionCalculator::new();
+        // 13 mils is 0.33 mm, 3.9 px at 300 DPI
+        assert_eq!(calc.pixels_per_module(calc.mils_to_mm(13.0), 300.0), 4);
+        assert_eq!(calc.pixels_per_module(0.05, 300.0), 1);
+    }
+
     #[test]
     fn test_a4_dimensions() {
         let calc = DimensionCalculator::new();
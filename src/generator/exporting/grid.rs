@@ -0,0 +1,229 @@
+use crate::generator::{
+    exporting::label_stock::LabelStock,
+    layout::{Alignment, Layout, Margins},
+};
+
+// Space between the margins and the gutters, in pixels at the layout DPI
+pub struct ContentArea {
+    pub left: f32,
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+    pub gutter_x: f32,
+    pub gutter_y: f32,
+}
+
+impl ContentArea {
+    // Auto margin uses the whole page without gutters
+    pub fn new(layout: &Layout) -> Self {
+        let (page_width, page_height) = layout.page_dimensions_px();
+        let mm_to_px = |mm: f32| mm / 25.4 * layout.dpi;
+        let (margins, gutter_x_mm, gutter_y_mm) = if layout.auto_margin {
+            (Margins::uniform(0.0), 0.0, 0.0)
+        } else {
+            (
+                layout.margins.clone(),
+                layout.gutter_x_mm,
+                layout.gutter_y_mm,
+            )
+        };
+        let left = mm_to_px(margins.left_mm);
+        let top = mm_to_px(margins.top_mm);
+        Self {
+            left,
+            top,
+            width: (page_width as f32 - left - mm_to_px(margins.right_mm)).max(1.0),
+            height: (page_height as f32 - top - mm_to_px(margins.bottom_mm)).max(1.0),
+            gutter_x: mm_to_px(gutter_x_mm),
+            gutter_y: mm_to_px(gutter_y_mm),
+        }
+    }
+}
+
+// Cells of one page in pixels at the layout DPI, filled row by row.
+// Pitch is the distance between the origins of neighbouring cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageGrid {
+    pub cols: u32,
+    pub rows: u32,
+    pub cell_width: f32,
+    pub cell_height: f32,
+    pub pitch_x: f32,
+    pub pitch_y: f32,
+    pub margin_x: f32,
+    pub margin_y: f32,
+    // Kept free inside every cell
+    pub inset: f32,
+}
+
+impl PageGrid {
+    // Label sheets have fixed cells, otherwise cells are the size of the first barcode
+    pub fn new(layout: &Layout, barcode_width: u32, barcode_height: u32) -> Self {
+        match &layout.label_stock {
+            Some(stock) => Self::label_sheet(stock, layout.dpi),
+            None => Self::packed(layout, barcode_width, barcode_height),
+        }
+    }
+
+    // As many barcode sized cells as fit between the margins, limited by max_rows and max_cols
+    fn packed(layout: &Layout, barcode_width: u32, barcode_height: u32) -> Self {
+        let ContentArea {
+            left,
+            top,
+            width: free_width,
+            height: free_height,
+            gutter_x,
+            gutter_y,
+        } = ContentArea::new(layout);
+        let (align_x, align_y) = if layout.auto_margin {
+            (Alignment::Center, Alignment::Center)
+        } else {
+            (layout.horizontal_alignment, layout.vertical_alignment)
+        };
+
+        // Barcodes larger than the free space are scaled down into a single cell
+        let cell_width = (barcode_width.max(1) as f32).min(free_width);
+        let cell_height = (barcode_height.max(1) as f32).min(free_height);
+        let fitting = |free: f32, cell: f32, gutter: f32, max: u32| {
+            let count = (((free + gutter) / (cell + gutter)).floor() as u32).max(1);
+            if max > 0 {
+                count.min(max)
+            } else {
+                count
+            }
+        };
+        let cols = fitting(free_width, cell_width, gutter_x, layout.max_cols);
+        let rows = fitting(free_height, cell_height, gutter_y, layout.max_rows);
+
+        let used_width = cols as f32 * cell_width + (cols - 1) as f32 * gutter_x;
+        let used_height = rows as f32 * cell_height + (rows - 1) as f32 * gutter_y;
+        let align = |alignment: Alignment, space: f32| match alignment {
+            Alignment::Start => 0.0,
+            Alignment::Center => (space / 2.0).floor(),
+            Alignment::End => space,
+        };
+        Self {
+            cols,
+            rows,
+            cell_width,
+            cell_height,
+            pitch_x: cell_width + gutter_x,
+            pitch_y: cell_height + gutter_y,
+            margin_x: left + align(align_x, free_width - used_width),
+            margin_y: top + align(align_y, free_height - used_height),
+            inset: 0.0,
+        }
+    }
+
+    fn label_sheet(stock: &LabelStock, dpi: f32) -> Self {
+        // Not rounded per cell, so the rounding error doesn't add up along the sheet
+        let mm_to_px = |mm: f32| mm / 25.4 * dpi;
+        Self {
+            cols: stock.cols,
+            rows: stock.rows,
+            cell_width: mm_to_px(stock.label_width_mm),
+            cell_height: mm_to_px(stock.label_height_mm),
+            pitch_x: mm_to_px(stock.pitch_x_mm),
+            pitch_y: mm_to_px(stock.pitch_y_mm),
+            margin_x: mm_to_px(stock.margin_left_mm),
+            margin_y: mm_to_px(stock.margin_top_mm),
+            inset: mm_to_px(stock.corner_radius_mm),
+        }
+    }
+
+    pub fn per_page(&self) -> usize {
+        (self.cols * self.rows) as usize
+    }
+
+    // Top left corner of the cell at index on its page
+    pub fn cell_origin(&self, index: usize) -> (f32, f32) {
+        let row = index as u32 / self.cols;
+        let col = index as u32 % self.cols;
+        (
+            self.margin_x + col as f32 * self.pitch_x,
+            self.margin_y + row as f32 * self.pitch_y,
+        )
+    }
+
+    // Position and scale of a barcode fitted into the cell keeping its aspect ratio, centered
+    pub fn fit(&self, index: usize, width: u32, height: u32) -> (f32, f32, f32) {
+        let (x, y) = self.cell_origin(index);
+        let free_width = (self.cell_width - 2.0 * self.inset).max(1.0);
+        let free_height = (self.cell_height - 2.0 * self.inset).max(1.0);
+        let scale = (free_width / width as f32).min(free_height / height as f32);
+        let new_width = (width as f32 * scale).floor();
+        let new_height = (height as f32 * scale).floor();
+        (
+            x + self.inset + ((free_width - new_width) / 2.0).floor(),
+            y + self.inset + ((free_height - new_height) / 2.0).floor(),
+            scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::exporting::label_stock;
+
+    // At 254 DPI a millimetre is 10 pixels, A4 is 2100 x 2970
+    fn layout_254_dpi() -> Layout {
+        let mut layout = Layout::new();
+        layout.dpi = 254.0;
+        layout
+    }
+
+    #[test]
+    fn test_auto_margin_cells() {
+        let grid = PageGrid::new(&layout_254_dpi(), 600, 400);
+        assert_eq!((grid.cols, grid.rows), (3, 7));
+        assert_eq!(grid.cell_origin(0), (150.0, 85.0));
+        assert_eq!(grid.cell_origin(4), (750.0, 485.0));
+        assert_eq!(grid.fit(4, 600, 400), (750.0, 485.0, 1.0));
+    }
+
+    #[test]
+    fn test_margins_gutters_and_limits() {
+        let mut layout = layout_254_dpi();
+        layout.auto_margin = false;
+        layout.margins = Margins::uniform(10.0);
+        layout.gutter_x_mm = 5.0;
+        layout.gutter_y_mm = 2.0;
+        layout.max_cols = 2;
+        layout.max_rows = 3;
+        layout.horizontal_alignment = Alignment::Center;
+        let grid = PageGrid::new(&layout, 600, 400);
+        assert_eq!((grid.cols, grid.rows, grid.per_page()), (2, 3, 6));
+        // 1900 px between the margins, the two columns with their gutter take 1250
+        assert_eq!(grid.cell_origin(0), (425.0, 100.0));
+        assert_eq!(grid.cell_origin(3), (1075.0, 520.0));
+
+        layout.max_cols = 0;
+        layout.max_rows = 0;
+        layout.horizontal_alignment = Alignment::Start;
+        layout.vertical_alignment = Alignment::End;
+        let grid = PageGrid::new(&layout, 600, 400);
+        // (1900 + 50) / 650 columns and (2770 + 20) / 420 rows
+        assert_eq!((grid.cols, grid.rows), (3, 6));
+        assert_eq!(grid.cell_origin(0), (100.0, 370.0));
+        assert_eq!(grid.cell_origin(17), (1400.0, 2470.0));
+    }
+
+    #[test]
+    fn test_label_cells() {
+        let mut layout = Layout::new();
+        layout.label_stock = Some(label_stock::get_label_stock("avery-l7160").unwrap());
+        let grid = PageGrid::new(&layout, 600, 300);
+        assert_eq!(grid.per_page(), 21);
+
+        // Second label in the third row: 7.21 + 66.04 mm, 15.15 + 2 * 38.1 mm
+        let (x, y) = grid.cell_origin(7);
+        assert!((x - 865.2).abs() < 0.1, "{}", x);
+        assert!((y - 1079.0).abs() < 0.1, "{}", y);
+
+        // Fitted inside the rounded corners, 2.5 mm are 29.5 px
+        let (x, _, scale) = grid.fit(0, 600, 300);
+        assert!((x - (85.2 + 29.5)).abs() < 1.0, "{}", x);
+        assert!(600.0 * scale <= grid.cell_width - 2.0 * grid.inset);
+    }
+}
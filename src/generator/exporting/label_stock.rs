@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+
+use crate::generator::exporting::paper::{self, Orientation, Paper};
+
+// Sheet of pre-cut labels. Pitch is the distance between the left (top) edges of neighbouring
+// labels, so it is the label size plus the gap between labels
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LabelStock {
+    pub id: String,
+    pub name: String,
+    pub paper: Paper,
+    pub orientation: Orientation,
+    pub label_width_mm: f32,
+    pub label_height_mm: f32,
+    pub rows: u32,
+    pub cols: u32,
+    pub pitch_x_mm: f32,
+    pub pitch_y_mm: f32,
+    pub margin_top_mm: f32,
+    pub margin_left_mm: f32,
+    // Barcodes are kept this far inside the label edges so they stay clear of the rounded corners
+    pub corner_radius_mm: f32,
+}
+
+impl LabelStock {
+    #[allow(clippy::too_many_arguments)]
+    fn preset(
+        id: &str,
+        name: &str,
+        paper: Paper,
+        (label_width_mm, label_height_mm): (f32, f32),
+        (cols, rows): (u32, u32),
+        (pitch_x_mm, pitch_y_mm): (f32, f32),
+        (margin_left_mm, margin_top_mm): (f32, f32),
+        corner_radius_mm: f32,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            paper,
+            orientation: Orientation::Portrait,
+            label_width_mm,
+            label_height_mm,
+            rows,
+            cols,
+            pitch_x_mm,
+            pitch_y_mm,
+            margin_top_mm,
+            margin_left_mm,
+            corner_radius_mm,
+        }
+    }
+
+    pub fn labels_per_sheet(&self) -> u32 {
+        self.rows * self.cols
+    }
+
+    // Every label has to be on the sheet and labels must not overlap
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.paper.validate()?;
+        if self.rows == 0 || self.cols == 0 {
+            anyhow::bail!(
+                "Label stock {} needs at least one row and column",
+                self.name
+            );
+        }
+        if self.label_width_mm <= 0.0 || self.label_height_mm <= 0.0 {
+            anyhow::bail!("Labels of {} must be larger than 0 mm", self.name);
+        }
+        // The corner insets meet in the middle of the label at half its shorter side
+        let max_radius_mm = self.label_width_mm.min(self.label_height_mm) / 2.0;
+        if self.corner_radius_mm.is_nan()
+            || self.corner_radius_mm < 0.0
+            || self.corner_radius_mm > max_radius_mm
+        {
+            anyhow::bail!(
+                "Corner radius of {} must be between 0 and {} mm, got {}",
+                self.name,
+                max_radius_mm,
+                self.corner_radius_mm
+            );
+        }
+        if (self.cols > 1 && self.pitch_x_mm < self.label_width_mm)
+            || (self.rows > 1 && self.pitch_y_mm < self.label_height_mm)
+        {
+            anyhow::bail!(
+                "Labels of {} overlap, pitch is below the label size",
+                self.name
+            );
+        }
+        if self.margin_left_mm < 0.0 || self.margin_top_mm < 0.0 {
+            anyhow::bail!("Margins of {} can't be negative", self.name);
+        }
+        // Published sheet measurements are rounded, allow a little overhang
+        let tolerance_mm = 0.5;
+        let (sheet_width_mm, sheet_height_mm) =
+            paper::get_paper_dimensions_mm(&self.paper, self.orientation);
+        let right =
+            self.margin_left_mm + (self.cols - 1) as f32 * self.pitch_x_mm + self.label_width_mm;
+        let bottom =
+            self.margin_top_mm + (self.rows - 1) as f32 * self.pitch_y_mm + self.label_height_mm;
+        if right > sheet_width_mm + tolerance_mm || bottom > sheet_height_mm + tolerance_mm {
+            anyhow::bail!("Labels of {} don't fit on the sheet", self.name);
+        }
+        Ok(())
+    }
+}
+
+// Common label sheets, measurements from the manufacturer templates
+pub fn get_label_stocks() -> Vec<LabelStock> {
+    vec![
+        LabelStock::preset(
+            "avery-l7160",
+            "Avery L7160, 21 per A4 sheet",
+            Paper::A4,
+            (63.5, 38.1),
+            (3, 7),
+            (66.04, 38.1),
+            (7.21, 15.15),
+            2.5,
+        ),
+        LabelStock::preset(
+            "avery-l7163",
+            "Avery L7163, 14 per A4 sheet",
+            Paper::A4,
+            (99.1, 38.1),
+            (2, 7),
+            (101.6, 38.1),
+            (4.65, 15.15),
+            2.5,
+        ),
+        LabelStock::preset(
+            "avery-l7651",
+            "Avery L7651, 65 per A4 sheet",
+            Paper::A4,
+            (38.1, 21.2),
+            (5, 13),
+            (40.64, 21.2),
+            (4.69, 10.7),
+            2.0,
+        ),
+        LabelStock::preset(
+            "avery-5160",
+            "Avery 5160, 30 per Letter sheet",
+            Paper::Letter,
+            (66.675, 25.4),
+            (3, 10),
+            (69.85, 25.4),
+            (4.7625, 12.7),
+            1.5875,
+        ),
+        LabelStock::preset(
+            "avery-5163",
+            "Avery 5163, 10 per Letter sheet",
+            Paper::Letter,
+            (101.6, 50.8),
+            (2, 5),
+            (106.3625, 50.8),
+            (3.96875, 12.7),
+            1.5875,
+        ),
+        LabelStock::preset(
+            "herma-4360",
+            "HERMA 4360, 24 per A4 sheet",
+            Paper::A4,
+            (70.0, 36.0),
+            (3, 8),
+            (70.0, 36.0),
+            (0.0, 4.5),
+            0.0,
+        ),
+    ]
+}
+
+pub fn get_label_stock(id: &str) -> anyhow::Result<LabelStock> {
+    get_label_stocks()
+        .into_iter()
+        .find(|stock| stock.id == id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown label stock {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_fit_their_sheets() {
+        for stock in get_label_stocks() {
+            assert!(stock.validate().is_ok(), "{} does not fit", stock.id);
+        }
+        let mut stock = get_label_stock("avery-l7160").unwrap();
+        stock.rows = 8;
+        assert!(stock.validate().is_err());
+    }
+
+    #[test]
+    fn test_corner_radius_within_label() {
+        // 63.5 x 38.1 mm labels
+        let mut stock = get_label_stock("avery-l7160").unwrap();
+        stock.corner_radius_mm = 19.05;
+        assert!(stock.validate().is_ok());
+        for radius in [-1.0, 19.1, f32::NAN] {
+            stock.corner_radius_mm = radius;
+            assert!(stock.validate().is_err(), "radius {} accepted", radius);
+        }
+    }
+}
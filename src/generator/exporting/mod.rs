@@ -1,3 +1,6 @@
+pub mod grid;
+pub mod label_stock;
+pub mod packing;
 pub mod paper;
 pub mod pdf_exporter;
 pub mod png_exporter;
@@ -0,0 +1,336 @@
+use serde::{Deserialize, Serialize};
+
+use crate::generator::{
+    exporting::grid::{ContentArea, PageGrid},
+    generator::GeneratedBarcode,
+    layout::Layout,
+};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Packing {
+    // Equal cells the size of the first barcode, the others are scaled into them
+    #[default]
+    Grid,
+    // Every barcode at its own size on shelves, tallest first, for mixed templates on one sheet
+    Shelf,
+}
+
+// Rectangle in pixels at the layout DPI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+// Barcode at index in the exported barcodes, drawn into rect scaled by scale.
+// Cell is the space reserved for it, outlined with grid borders
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub index: usize,
+    pub rect: PageRect,
+    pub scale: f32,
+    pub cell: PageRect,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PageUtilisation {
+    pub page: usize,
+    pub barcodes: usize,
+    // Share of the space between the margins covered by barcodes, 0 to 1
+    pub utilisation: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackingReport {
+    pub pages: Vec<PageUtilisation>,
+    pub utilisation: f32,
+}
+
+// Placements of every barcode, one list per page
+pub fn place_barcodes(
+    layout: &Layout,
+    barcodes: &[GeneratedBarcode],
+) -> anyhow::Result<Vec<Vec<Placement>>> {
+    let sizes: Vec<(u32, u32)> = barcodes
+        .iter()
+        .map(|barcode| barcode.buffer.dimensions())
+        .collect();
+    match (&layout.label_stock, layout.packing) {
+        (None, Packing::Shelf) => Ok(place_on_shelves(&ContentArea::new(layout), &sizes)),
+        _ => place_in_grid(layout, &sizes),
+    }
+}
+
+// Cells are filled in order, on the first page only from start_cell on and without skipped_cells
+// so partly used label sheets can be printed again
+fn place_in_grid(layout: &Layout, sizes: &[(u32, u32)]) -> anyhow::Result<Vec<Vec<Placement>>> {
+    let Some(&(first_width, first_height)) = sizes.first() else {
+        return Ok(Vec::new());
+    };
+    let grid = PageGrid::new(layout, first_width, first_height);
+    let first_page_cells: Vec<usize> = (0..grid.per_page())
+        .filter(|&cell| {
+            cell >= layout.start_cell as usize && !layout.skipped_cells.contains(&(cell as u32))
+        })
+        .collect();
+    if first_page_cells.is_empty() {
+        anyhow::bail!(
+            "Start cell and skipped cells leave none of the {} cells on the first page",
+            grid.per_page()
+        );
+    }
+    let all_cells: Vec<usize> = (0..grid.per_page()).collect();
+
+    let mut pages: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut indices = 0..sizes.len();
+    while !indices.is_empty() {
+        let cells = if pages.is_empty() {
+            &first_page_cells
+        } else {
+            &all_cells
+        };
+        pages.push(cells.iter().copied().zip(indices.by_ref()).collect());
+    }
+    Ok(pages
+        .into_iter()
+        .map(|page| {
+            page.into_iter()
+                .map(|(cell, index)| {
+                    let (width, height) = sizes[index];
+                    let (x, y, scale) = grid.fit(cell, width, height);
+                    let (cell_x, cell_y) = grid.cell_origin(cell);
+                    Placement {
+                        index,
+                        rect: PageRect {
+                            x,
+                            y,
+                            width: (width as f32 * scale).floor(),
+                            height: (height as f32 * scale).floor(),
+                        },
+                        scale,
+                        cell: PageRect {
+                            x: cell_x,
+                            y: cell_y,
+                            width: grid.cell_width,
+                            height: grid.cell_height,
+                        },
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+// Shelf on the current page, barcodes are added left to right
+struct Shelf {
+    y: f32,
+    height: f32,
+    used_width: f32,
+}
+
+// First fit decreasing height: barcodes sorted tallest first go on the first shelf with room,
+// a new shelf is opened below the last one and a new page when the page is full
+fn place_on_shelves(area: &ContentArea, sizes: &[(u32, u32)]) -> Vec<Vec<Placement>> {
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    order.sort_by_key(|&index| std::cmp::Reverse(sizes[index].1));
+
+    let mut pages: Vec<Vec<Placement>> = Vec::new();
+    let mut page: Vec<Placement> = Vec::new();
+    let mut shelves: Vec<Shelf> = Vec::new();
+    for index in order {
+        // Barcodes larger than the area are scaled down to fit on an empty page
+        let (width, height) = sizes[index];
+        let scale = (area.width / width.max(1) as f32)
+            .min(area.height / height.max(1) as f32)
+            .min(1.0);
+        let width = (width as f32 * scale).floor();
+        let height = (height as f32 * scale).floor();
+
+        let gap = |shelf: &Shelf| {
+            if shelf.used_width > 0.0 {
+                area.gutter_x
+            } else {
+                0.0
+            }
+        };
+        let fits = |shelf: &Shelf| {
+            height <= shelf.height && shelf.used_width + gap(shelf) + width <= area.width
+        };
+        let shelf_index = match shelves.iter().position(fits) {
+            Some(shelf_index) => shelf_index,
+            None => {
+                let y = shelves
+                    .last()
+                    .map_or(0.0, |last| last.y + last.height + area.gutter_y);
+                let y = if y + height <= area.height {
+                    y
+                } else {
+                    pages.push(std::mem::take(&mut page));
+                    shelves.clear();
+                    0.0
+                };
+                shelves.push(Shelf {
+                    y,
+                    height,
+                    used_width: 0.0,
+                });
+                shelves.len() - 1
+            }
+        };
+
+        let shelf = &mut shelves[shelf_index];
+        let x = shelf.used_width + gap(shelf);
+        shelf.used_width = x + width;
+        let cell = PageRect {
+            x: area.left + x,
+            y: area.top + shelf.y,
+            width,
+            height,
+        };
+        page.push(Placement {
+            index,
+            rect: cell,
+            scale,
+            cell,
+        });
+    }
+    if !page.is_empty() {
+        pages.push(page);
+    }
+    pages
+}
+
+// How much of every page the barcodes cover, label sheets count their whole page
+pub fn packing_report(layout: &Layout, pages: &[Vec<Placement>]) -> PackingReport {
+    let page_area = match &layout.label_stock {
+        Some(_) => {
+            let (width, height) = layout.page_dimensions_px();
+            width as f32 * height as f32
+        }
+        None => {
+            let area = ContentArea::new(layout);
+            area.width * area.height
+        }
+    };
+    let pages: Vec<PageUtilisation> = pages
+        .iter()
+        .enumerate()
+        .map(|(page, placements)| {
+            let covered: f32 = placements
+                .iter()
+                .map(|placement| placement.rect.width * placement.rect.height)
+                .sum();
+            PageUtilisation {
+                page: page + 1,
+                barcodes: placements.len(),
+                utilisation: (covered / page_area).min(1.0),
+            }
+        })
+        .collect();
+    let utilisation = if pages.is_empty() {
+        0.0
+    } else {
+        pages.iter().map(|page| page.utilisation).sum::<f32>() / pages.len() as f32
+    };
+    PackingReport { pages, utilisation }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::exporting::label_stock;
+
+    // 100 x 100 px between the margins with 10 px gutters
+    fn area() -> ContentArea {
+        ContentArea {
+            left: 5.0,
+            top: 5.0,
+            width: 100.0,
+            height: 100.0,
+            gutter_x: 10.0,
+            gutter_y: 10.0,
+        }
+    }
+
+    #[test]
+    fn test_shelves_take_mixed_sizes() {
+        // A wide linear code, two square matrix codes and one too large for the page
+        let sizes = [(90, 30), (40, 40), (40, 40), (200, 160)];
+        let pages = place_on_shelves(&area(), &sizes);
+        assert_eq!(pages.len(), 2);
+
+        // The oversized one is scaled to the page width and fills the first page
+        assert_eq!(pages[0].len(), 1);
+        assert_eq!(pages[0][0].index, 3);
+        assert_eq!(pages[0][0].scale, 0.5);
+
+        // Squares share the first shelf, the linear code goes on a shelf below
+        let positions: Vec<(usize, f32, f32)> = pages[1]
+            .iter()
+            .map(|placement| (placement.index, placement.rect.x, placement.rect.y))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(1, 5.0, 5.0), (2, 55.0, 5.0), (0, 5.0, 55.0)]
+        );
+    }
+
+    #[test]
+    fn test_report_utilisation() {
+        let mut layout = Layout::new();
+        layout.dpi = 254.0;
+        layout.packing = Packing::Shelf;
+        let placement = |index: usize, width: f32, height: f32| Placement {
+            index,
+            rect: PageRect {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+            },
+            scale: 1.0,
+            cell: PageRect {
+                x: 0.0,
+                y: 0.0,
+                width,
+                height,
+            },
+        };
+        // A4 at 254 DPI is 2100 x 2970 px without margins
+        let pages = vec![
+            vec![placement(0, 2100.0, 1485.0), placement(1, 1050.0, 1485.0)],
+            vec![placement(2, 1050.0, 297.0)],
+        ];
+        let report = packing_report(&layout, &pages);
+        assert_eq!(report.pages[0].barcodes, 2);
+        assert!((report.pages[0].utilisation - 0.75).abs() < 1e-4);
+        assert!((report.pages[1].utilisation - 0.05).abs() < 1e-4);
+        assert!((report.utilisation - 0.4).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_first_page_starts_at_cell() {
+        let mut layout = Layout::new();
+        layout.label_stock = Some(label_stock::get_label_stock("avery-l7163").unwrap());
+        layout.start_cell = 3;
+        layout.skipped_cells = vec![5];
+        // 14 labels per sheet, 10 are free on the first one
+        let pages = place_in_grid(&layout, &[(600, 300); 15]).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].len(), 10);
+        assert_eq!(pages[1].len(), 5);
+
+        let grid = PageGrid::new(&layout, 600, 300);
+        let cell_of = |placement: &Placement| (placement.cell.x, placement.cell.y);
+        assert_eq!(cell_of(&pages[0][0]), grid.cell_origin(3));
+        assert_eq!(cell_of(&pages[0][2]), grid.cell_origin(6));
+        assert_eq!(pages[0][2].index, 2);
+        assert_eq!(cell_of(&pages[1][0]), grid.cell_origin(0));
+        assert_eq!(pages[1][0].index, 10);
+
+        layout.start_cell = 14;
+        assert!(place_in_grid(&layout, &[(600, 300)]).is_err());
+    }
+}
@@ -1,20 +1,147 @@
 use crate::generator::calculator::DimensionCalculator;
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
 pub enum Paper {
+    A0,
+    A1,
+    A2,
+    A3,
     A4,
+    A5,
+    A6,
+    A7,
+    B0,
+    B1,
+    B2,
+    B3,
+    B4,
+    B5,
+    B6,
+    Letter,
+    Legal,
+    Tabloid,
+    Custom { width_mm: f32, height_mm: f32 },
 }
 
-pub fn get_paper_dimensions_mm(paper: &Paper, dpi: f32) -> (f32, f32) {
-    match paper {
-        Paper::A4 => (
-            DimensionCalculator::new().px_to_mm(2480, dpi),
-            DimensionCalculator::new().px_to_mm(3508, dpi),
-        ),
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+}
+
+impl Paper {
+    // Portrait width and height in mm, custom sizes as given
+    fn portrait_mm(&self) -> (f32, f32) {
+        match self {
+            Paper::A0 => (841.0, 1189.0),
+            Paper::A1 => (594.0, 841.0),
+            Paper::A2 => (420.0, 594.0),
+            Paper::A3 => (297.0, 420.0),
+            Paper::A4 => (210.0, 297.0),
+            Paper::A5 => (148.0, 210.0),
+            Paper::A6 => (105.0, 148.0),
+            Paper::A7 => (74.0, 105.0),
+            Paper::B0 => (1000.0, 1414.0),
+            Paper::B1 => (707.0, 1000.0),
+            Paper::B2 => (500.0, 707.0),
+            Paper::B3 => (353.0, 500.0),
+            Paper::B4 => (250.0, 353.0),
+            Paper::B5 => (176.0, 250.0),
+            Paper::B6 => (125.0, 176.0),
+            Paper::Letter => (215.9, 279.4),
+            Paper::Legal => (215.9, 355.6),
+            Paper::Tabloid => (279.4, 431.8),
+            Paper::Custom {
+                width_mm,
+                height_mm,
+            } => (*width_mm, *height_mm),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let (width, height) = self.portrait_mm();
+        if !width.is_finite() || !height.is_finite() || width <= 0.0 || height <= 0.0 {
+            anyhow::bail!("Paper width and height must be greater than 0 mm");
+        }
+        Ok(())
+    }
+}
+
+// Width and height of the page in mm, landscape swaps the portrait sides
+pub fn get_paper_dimensions_mm(paper: &Paper, orientation: Orientation) -> (f32, f32) {
+    let (width, height) = paper.portrait_mm();
+    match orientation {
+        Orientation::Portrait => (width, height),
+        Orientation::Landscape => (height, width),
     }
 }
-pub fn get_paper_dimensions_px(paper: &Paper) -> (u32, u32) {
-    match paper {
-        Paper::A4 => (2480 as u32, 3508 as u32),
+
+pub fn get_paper_dimensions_px(paper: &Paper, orientation: Orientation, dpi: f32) -> (u32, u32) {
+    let calculator = DimensionCalculator::new();
+    let (width_mm, height_mm) = get_paper_dimensions_mm(paper, orientation);
+    (
+        calculator.mm_to_px(width_mm, dpi),
+        calculator.mm_to_px(height_mm, dpi),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paper_dimensions_px() {
+        assert_eq!(
+            get_paper_dimensions_px(&Paper::A4, Orientation::Portrait, 300.0),
+            (2480, 3508)
+        );
+        assert_eq!(
+            get_paper_dimensions_px(&Paper::Letter, Orientation::Landscape, 300.0),
+            (3300, 2550)
+        );
+        assert_eq!(
+            get_paper_dimensions_px(&Paper::A6, Orientation::Portrait, 600.0),
+            (2480, 3496)
+        );
+        assert_eq!(
+            get_paper_dimensions_mm(&Paper::A0, Orientation::Portrait),
+            (841.0, 1189.0)
+        );
+        assert_eq!(
+            get_paper_dimensions_mm(&Paper::B3, Orientation::Landscape),
+            (500.0, 353.0)
+        );
+    }
+
+    #[test]
+    fn test_custom_paper_orientation() {
+        let label = Paper::Custom {
+            width_mm: 100.0,
+            height_mm: 50.0,
+        };
+        // Custom sizes keep the sides they were given
+        assert_eq!(
+            get_paper_dimensions_mm(&label, Orientation::Portrait),
+            (100.0, 50.0)
+        );
+        assert_eq!(
+            get_paper_dimensions_mm(&label, Orientation::Landscape),
+            (50.0, 100.0)
+        );
+        assert!(label.validate().is_ok());
+        for (width_mm, height_mm) in [
+            (0.0, 50.0),
+            (f32::NAN, 50.0),
+            (100.0, f32::NAN),
+            (f32::INFINITY, 50.0),
+        ] {
+            assert!(Paper::Custom {
+                width_mm,
+                height_mm
+            }
+            .validate()
+            .is_err());
+        }
     }
 }
@@ -1,11 +1,289 @@
-#[derive(Default)]
-pub struct PdfExporter {}
+use std::collections::HashMap;
+
+use printpdf::{
+    path::PaintMode, Color, IndirectFontRef, Mm, PdfDocument, PdfDocumentReference,
+    PdfLayerReference, Rect, Rgb,
+};
+
+use crate::generator::{
+    exporting::packing::{self, PageRect, Placement},
+    fonts::{EmMetrics, FontRegistry},
+    generator::GeneratedBarcode,
+    job::Job,
+    layout::Layout,
+};
+
+pub struct PdfExporter {
+    pub layout: Layout,
+    job: Option<Job>,
+}
+
+// Font embedded once per document, with the metrics needed to match the raster text placement
+struct EmbeddedFont {
+    font_ref: IndirectFontRef,
+    metrics: EmMetrics,
+}
 
 impl PdfExporter {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(layout: Layout) -> Self {
+        Self { layout, job: None }
+    }
+
+    pub fn with_job(self, job: Option<Job>) -> Self {
+        Self { job, ..self }
+    }
+
+    // Creates single page PDF for preview
+    pub fn create_export_preview(&self, barcodes: &[GeneratedBarcode]) -> anyhow::Result<Vec<u8>> {
+        self.create_document(barcodes, Some(1))
+    }
+
+    // Creates one PDF containing every page needed for the barcodes
+    pub fn create_full_export(&self, barcodes: &[GeneratedBarcode]) -> anyhow::Result<Vec<u8>> {
+        self.create_document(barcodes, None)
+    }
+
+    fn create_document(
+        &self,
+        barcodes: &[GeneratedBarcode],
+        page_limit: Option<usize>,
+    ) -> anyhow::Result<Vec<u8>> {
+        if barcodes.is_empty() {
+            anyhow::bail!("No barcodes to export");
+        }
+        // Same placements as the PNG exporter so both outputs match
+        let pages = packing::place_barcodes(&self.layout, barcodes)?;
+        let (paper_width_mm, paper_height_mm) = self.layout.page_dimensions_mm();
+
+        let (document, first_page, first_layer) = PdfDocument::new(
+            "Barcodes",
+            Mm(paper_width_mm),
+            Mm(paper_height_mm),
+            "Barcodes",
+        );
+        let mut fonts: HashMap<String, EmbeddedFont> = HashMap::new();
+
+        for (page_index, placements) in pages.iter().enumerate() {
+            if page_limit.is_some_and(|limit| page_index >= limit) {
+                break;
+            }
+            if let Some(job) = &self.job {
+                job.check_cancelled()?;
+                job.page_started(page_index + 1);
+            }
+            let layer = if page_index == 0 {
+                document.get_page(first_page).get_layer(first_layer)
+            } else {
+                let (page, layer) = document.add_page(
+                    Mm(paper_width_mm),
+                    Mm(paper_height_mm),
+                    format!("Page {}", page_index + 1),
+                );
+                document.get_page(page).get_layer(layer)
+            };
+
+            for placement in placements {
+                self.place_barcode(
+                    &document,
+                    &mut fonts,
+                    &layer,
+                    &barcodes[placement.index],
+                    placement,
+                    paper_height_mm,
+                )?;
+                if self.layout.grid_borders {
+                    self.draw_cell_border(&layer, &placement.cell, paper_height_mm);
+                }
+            }
+        }
+
+        document
+            .save_to_bytes()
+            .map_err(|e| anyhow::anyhow!("Failed to write PDF: {}", e))
+    }
+
+    fn place_barcode(
+        &self,
+        document: &PdfDocumentReference,
+        fonts: &mut HashMap<String, EmbeddedFont>,
+        layer: &PdfLayerReference,
+        barcode: &GeneratedBarcode,
+        placement: &Placement,
+        paper_height_mm: f32,
+    ) -> anyhow::Result<()> {
+        let buffer = &barcode.buffer;
+        let (offset_x, offset_y, scale) = (placement.rect.x, placement.rect.y, placement.scale);
+
+        // PDF origin is bottom left, pixel origin is top left
+        let to_x_mm = |px: f32| self.px_to_mm(offset_x + px * scale);
+        let to_y_mm = |px: f32| paper_height_mm - self.px_to_mm(offset_y + px * scale);
+
+        // White paper needs no background, other colours fill the whole barcode image
+        if !barcode.transparent_background && barcode.background != image::Rgb([255, 255, 255]) {
+            layer.set_fill_color(pdf_color(barcode.background));
+            layer.add_rect(Rect::new(
+                Mm(to_x_mm(0.0)),
+                Mm(to_y_mm(buffer.height() as f32)),
+                Mm(to_x_mm(buffer.width() as f32)),
+                Mm(to_y_mm(0.0)),
+            ));
+        }
+
+        // Draw the bars as rectangles, one per run of dark modules
+        layer.set_fill_color(pdf_color(barcode.foreground));
+        for (left, top, width, height) in barcode.dark_rects() {
+            layer.add_rect(Rect::new(
+                Mm(to_x_mm(left)),
+                Mm(to_y_mm(top + height)),
+                Mm(to_x_mm(left + width)),
+                Mm(to_y_mm(top)),
+            ));
+        }
+
+        for text in &barcode.texts {
+            if !fonts.contains_key(&text.font) {
+                fonts.insert(text.font.clone(), self.embed_font(document, &text.font)?);
+            }
+            let font = &fonts[&text.font];
+            let text_size = text.text_size as f32;
+            let font_size_pt =
+                self.px_to_mm(text_size * font.metrics.em_per_height * scale) / 25.4 * 72.0;
+            let baseline = text.y as f32 + text_size * font.metrics.ascent_per_height;
+            layer.set_fill_color(pdf_color(text.text_color));
+            layer.use_text(
+                text.text.as_str(),
+                font_size_pt,
+                Mm(to_x_mm(text.x as f32)),
+                Mm(to_y_mm(baseline)),
+                &font.font_ref,
+            );
+        }
+        Ok(())
+    }
+
+    fn embed_font(
+        &self,
+        document: &PdfDocumentReference,
+        font: &str,
+    ) -> anyhow::Result<EmbeddedFont> {
+        let loaded = FontRegistry::global().load(font)?;
+        let font_ref = document
+            .add_external_font_with_subsetting(loaded.bytes.as_slice(), true)
+            .map_err(|e| anyhow::anyhow!("Failed to embed font {}: {}", font, e))?;
+        Ok(EmbeddedFont {
+            font_ref,
+            metrics: loaded.em,
+        })
+    }
+
+    fn px_to_mm(&self, px: f32) -> f32 {
+        px / self.layout.dpi * 25.4
+    }
+
+    fn draw_cell_border(&self, layer: &PdfLayerReference, cell: &PageRect, paper_height_mm: f32) {
+        let left = self.px_to_mm(cell.x);
+        let right = self.px_to_mm(cell.x + cell.width);
+        let top = paper_height_mm - self.px_to_mm(cell.y);
+        let bottom = paper_height_mm - self.px_to_mm(cell.y + cell.height);
+
+        let border_color = 211.0 / 255.0;
+        layer.set_outline_color(Color::Rgb(Rgb::new(
+            border_color,
+            border_color,
+            border_color,
+            None,
+        )));
+        layer.set_outline_thickness(0.5);
+        layer.add_rect(
+            Rect::new(Mm(left), Mm(bottom), Mm(right), Mm(top)).with_mode(PaintMode::Stroke),
+        );
+    }
+}
+
+fn pdf_color(color: image::Rgb<u8>) -> Color {
+    Color::Rgb(Rgb::new(
+        color[0] as f32 / 255.0,
+        color[1] as f32 / 255.0,
+        color[2] as f32 / 255.0,
+        None,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::barcode_config::{BarcodeConfigBuilder, BarcodeConfigInternal};
+    use crate::generator::generator::Generator;
+    use printpdf::lopdf::{content::Content, Document};
+    use zxingcpp::BarcodeFormat;
+
+    fn barcodes(count: usize) -> Vec<GeneratedBarcode> {
+        let config: BarcodeConfigInternal = BarcodeConfigBuilder::new()
+            .set_format(BarcodeFormat::Code128)
+            .build()
+            .try_into()
+            .unwrap();
+        let generator = Generator::new();
+        (0..count)
+            .map(|i| {
+                generator
+                    .render_barcode(&format!("ROW{}", i), &config, "", 300.0)
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    // Two barcodes on every page
+    fn layout() -> Layout {
+        let mut layout = Layout::new();
+        layout.max_cols = 2;
+        layout.max_rows = 1;
+        layout
     }
-    pub fn create_export_preview(&self) -> anyhow::Result<Vec<u8>> {
-        todo!()
+
+    #[test]
+    fn test_pages_for_every_barcode() {
+        let bytes = PdfExporter::new(layout())
+            .create_full_export(&barcodes(5))
+            .unwrap();
+        let document = Document::load_mem(&bytes).unwrap();
+        assert_eq!(document.get_pages().len(), 3);
+
+        // The preview stops after the first page
+        let bytes = PdfExporter::new(layout())
+            .create_export_preview(&barcodes(5))
+            .unwrap();
+        let document = Document::load_mem(&bytes).unwrap();
+        assert_eq!(document.get_pages().len(), 1);
+    }
+
+    #[test]
+    fn test_bars_are_vector_rectangles() {
+        let barcodes = barcodes(2);
+        let bytes = PdfExporter::new(layout())
+            .create_full_export(&barcodes)
+            .unwrap();
+        let document = Document::load_mem(&bytes).unwrap();
+        let (_, &page_id) = document.get_pages().iter().next().unwrap();
+        let content = Content::decode(&document.get_page_content(page_id).unwrap()).unwrap();
+        let operators: Vec<&str> = content
+            .operations
+            .iter()
+            .map(|operation| operation.operator.as_str())
+            .collect();
+
+        // No image is drawn or embedded, every bar is a filled path
+        assert!(!operators.contains(&"Do"));
+        assert!(!document
+            .objects
+            .values()
+            .any(|object| object.as_stream().is_ok_and(|stream| stream
+                .dict
+                .get(b"Subtype")
+                .and_then(|subtype| subtype.as_name())
+                .is_ok_and(|subtype| subtype == b"Image"))));
+        let bars: usize = barcodes.iter().map(|b| b.dark_rects().len()).sum();
+        let fills = operators.iter().filter(|op| **op == "f").count();
+        assert!(fills >= bars);
     }
 }
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use ab_glyph::{Font as _, FontArc};
+use fontdue::Font;
+
+// Fonts shipped inside the binary, so texts render the same whatever the working directory is
+const EMBEDDED_FONTS: [(&str, &[u8]); 4] = [
+    ("DejaVuSans", include_bytes!("../../assets/DejaVuSans.ttf")),
+    ("Arial", include_bytes!("../../assets/Arial.ttf")),
+    ("Courier", include_bytes!("../../assets/Courier.ttf")),
+    ("Times", include_bytes!("../../assets/Times.ttf")),
+];
+
+// Used when a family can't be found anywhere
+pub const FALLBACK_FONT: &str = "DejaVuSans";
+
+// Ratios between the ab_glyph pixel scale (ascent - descent) and em based font sizes used by PDF and SVG
+#[derive(Debug, Clone, Copy)]
+pub struct EmMetrics {
+    pub em_per_height: f32,
+    pub ascent_per_height: f32,
+}
+
+impl EmMetrics {
+    fn from_font(font: &FontArc) -> Self {
+        let height = font.height_unscaled();
+        Self {
+            em_per_height: font.units_per_em().unwrap_or(height) / height,
+            ascent_per_height: font.ascent_unscaled() / height,
+        }
+    }
+}
+
+// One font file parsed for every renderer: ab_glyph draws, fontdue measures, the bytes are embedded in PDFs
+pub struct LoadedFont {
+    pub family: String,
+    pub bytes: Arc<Vec<u8>>,
+    pub glyphs: FontArc,
+    pub metrics: Font,
+    pub em: EmMetrics,
+}
+
+impl LoadedFont {
+    fn parse(family: &str, bytes: Vec<u8>) -> anyhow::Result<Self> {
+        let metrics = Font::from_bytes(bytes.as_slice(), fontdue::FontSettings::default())
+            .map_err(|e| anyhow::anyhow!("Failed to parse font {}: {}", family, e))?;
+        let glyphs = FontArc::try_from_vec(bytes.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to parse font {}: {}", family, e))?;
+        Ok(Self {
+            family: family.to_string(),
+            em: EmMetrics::from_font(&glyphs),
+            bytes: Arc::new(bytes),
+            glyphs,
+            metrics,
+        })
+    }
+}
+
+// Resolves font family names to parsed fonts. Lookup order is user font directories,
+// the embedded fonts, then system fonts, and DejaVuSans when nothing matches.
+// Each family is loaded once and shared
+pub struct FontRegistry {
+    user_dirs: RwLock<Vec<PathBuf>>,
+    system_dirs: Vec<PathBuf>,
+    fonts: Mutex<HashMap<String, Arc<LoadedFont>>>,
+}
+
+impl FontRegistry {
+    pub fn new() -> Self {
+        Self {
+            user_dirs: RwLock::new(Vec::new()),
+            system_dirs: system_font_dirs(),
+            fonts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Registry shared by the generator and the exporters
+    pub fn global() -> &'static FontRegistry {
+        static REGISTRY: OnceLock<FontRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(FontRegistry::new)
+    }
+
+    // Fonts in the directory take priority over embedded and system fonts
+    pub fn add_font_dir(&self, dir: impl Into<PathBuf>) {
+        let dir = dir.into();
+        // Fonts lock first, same order as load, so the two can't deadlock
+        let mut fonts = self.fonts.lock().unwrap();
+        let mut user_dirs = self.user_dirs.write().unwrap();
+        if !user_dirs.contains(&dir) {
+            user_dirs.push(dir);
+            // Families resolved before may now come from the new directory
+            fonts.clear();
+        }
+    }
+
+    pub fn load(&self, family: &str) -> anyhow::Result<Arc<LoadedFont>> {
+        let key = normalize_family(family);
+        let mut fonts = self.fonts.lock().unwrap();
+        if let Some(font) = fonts.get(&key) {
+            return Ok(font.clone());
+        }
+        let font = Arc::new(self.resolve(family, &key)?);
+        fonts.insert(key, font.clone());
+        Ok(font)
+    }
+
+    fn resolve(&self, family: &str, key: &str) -> anyhow::Result<LoadedFont> {
+        let user_dirs = self.user_dirs.read().unwrap();
+        if let Some(path) = find_font_file(&user_dirs, key) {
+            return LoadedFont::parse(family, fs::read(path)?);
+        }
+        if let Some((_, bytes)) = EMBEDDED_FONTS
+            .iter()
+            .find(|(name, _)| normalize_family(name) == key)
+        {
+            return LoadedFont::parse(family, bytes.to_vec());
+        }
+        if let Some(path) = find_font_file(&self.system_dirs, key) {
+            return LoadedFont::parse(family, fs::read(path)?);
+        }
+        log::warn!("Font {} not found, using {}", family, FALLBACK_FONT);
+        let (_, bytes) = EMBEDDED_FONTS[0];
+        LoadedFont::parse(FALLBACK_FONT, bytes.to_vec())
+    }
+}
+
+// "DejaVu Sans", "dejavu-sans" and "DejaVuSans" all name the same family
+fn normalize_family(family: &str) -> String {
+    family
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+// Match files by name, Family.ttf or Family-Regular.ttf, searching subdirectories too
+fn find_font_file(dirs: &[PathBuf], key: &str) -> Option<PathBuf> {
+    let regular = format!("{}regular", key);
+    dirs.iter().find_map(|dir| {
+        find_in_dir(dir, &|path: &Path| {
+            let is_font = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("ttf") || ext.eq_ignore_ascii_case("otf")
+                });
+            let stem = path
+                .file_stem()
+                .map(|stem| normalize_family(&stem.to_string_lossy()))
+                .unwrap_or_default();
+            is_font && (stem == key || stem == regular)
+        })
+    })
+}
+
+fn find_in_dir(dir: &Path, matches: &dyn Fn(&Path) -> bool) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir).ok()?;
+    let mut subdirs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        } else if matches(&path) {
+            return Some(path);
+        }
+    }
+    subdirs
+        .iter()
+        .find_map(|subdir| find_in_dir(subdir, matches))
+}
+
+fn system_font_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if cfg!(target_os = "windows") {
+        let windir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
+        dirs.push(PathBuf::from(windir).join("Fonts"));
+        if let Ok(local) = std::env::var("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Microsoft\\Windows\\Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(PathBuf::from(home).join("Library/Fonts"));
+        }
+        dirs.push(PathBuf::from("/Library/Fonts"));
+        dirs.push(PathBuf::from("/System/Library/Fonts"));
+    } else {
+        if let Ok(home) = std::env::var("HOME") {
+            dirs.push(PathBuf::from(&home).join(".local/share/fonts"));
+            dirs.push(PathBuf::from(&home).join(".fonts"));
+        }
+        dirs.push(PathBuf::from("/usr/local/share/fonts"));
+        dirs.push(PathBuf::from("/usr/share/fonts"));
+    }
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_font_by_family_name() {
+        let registry = FontRegistry::new();
+        let font = registry.load("DejaVu Sans").unwrap();
+        assert_eq!(font.family, "DejaVu Sans");
+        assert!(Arc::ptr_eq(&font, &registry.load("dejavusans").unwrap()));
+    }
+
+    #[test]
+    fn test_unknown_family_falls_back() {
+        let registry = FontRegistry::new();
+        let font = registry.load("No Such Font Family").unwrap();
+        assert_eq!(font.family, FALLBACK_FONT);
+    }
+}
@@ -1,12 +1,21 @@
 use serde::Serialize;
 
 use crate::generator::{
-    barcode_config::BarcodeConfig,
-    generator::Generator,
+    barcode_config::{BarcodeConfig, BarcodeConfigInternal},
+    bulk_generator::{BulkGenerationResult, BulkGenerator},
+    bulk_generator_config::BulkGeneratorConfig,
+    exporting::label_stock,
+    generator::{GeneratedBarcode, Generator},
     importer::{BarcodeImportRowCSV, Importer},
+    job::Job,
+    layout::{ExportManager, Layout},
+    quality::QualityGrader,
     templates::{self, Template},
 };
 
+// Single barcodes are generated at this DPI for the preview and download
+const PREVIEW_DPI: f32 = 300.0;
+
 // COntains everything needed to communicate with frontend
 #[derive(Clone)]
 pub struct FrontendInterface {}
@@ -29,29 +38,67 @@ impl FrontendInterface {
         );
 
         // Convert the serializable config to internal config
-        let internal_config: crate::generator::barcode_config::BarcodeConfigInternal =
-            config.into();
-
-        let generated_barcode = generator
-            .generate_barcode_png(
-                &internal_config.data.clone(),
-                internal_config,
-                &temp_filename,
-            )
-            .unwrap();
-
-        let mut png_bytes = Vec::new();
-        generated_barcode
-            .buffer
-            .write_with_encoder(image::codecs::png::PngEncoder::new(&mut png_bytes))
-            .map_err(|e| e.to_string())
-            .unwrap();
+        let internal_config: BarcodeConfigInternal = config.try_into()?;
+
+        generator.generate_barcode_png_with_dpi(
+            &internal_config.data.clone(),
+            internal_config,
+            &temp_filename,
+            PREVIEW_DPI,
+        )?;
+
+        // Send the saved file so the DPI metadata and transparency reach the frontend
+        let png_bytes = std::fs::read(&temp_filename)?;
 
         // Clean up the temporary file
         let _ = std::fs::remove_file(&temp_filename);
         Ok(png_bytes)
     }
 
+    // Printed size of the barcode at the preview DPI, data is null unless an X-dimension is set
+    pub fn measure_barcode(&self, config: BarcodeConfig) -> JsonResponse {
+        let result = BarcodeConfigInternal::try_from(config)
+            .map_err(anyhow::Error::from)
+            .and_then(|config| {
+                Generator::new().render_barcode(&config.data, &config, "", PREVIEW_DPI)
+            });
+        match result {
+            Ok(generated_barcode) => JsonResponse {
+                success: true,
+                message: "Barcode measured successfully".to_string(),
+                data: Some(serde_json::to_value(generated_barcode.module_sizing).unwrap()),
+            },
+            Err(e) => JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            },
+        }
+    }
+
+    // Print quality estimate of the barcode at the preview DPI, linear formats only
+    pub fn grade_barcode(&self, config: BarcodeConfig) -> JsonResponse {
+        let result = BarcodeConfigInternal::try_from(config)
+            .map_err(anyhow::Error::from)
+            .and_then(|config| {
+                let generated_barcode =
+                    Generator::new().render_barcode(&config.data, &config, "", PREVIEW_DPI)?;
+                QualityGrader::new().grade(&generated_barcode, config.format)
+            });
+        match result {
+            Ok(report) => JsonResponse {
+                success: true,
+                message: format!("Estimated print quality grade {}", report.grade),
+                data: Some(serde_json::to_value(&report).unwrap()),
+            },
+            Err(e) => JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            },
+        }
+    }
+
     pub fn save_template(&self, template: Template) -> JsonResponse {
         let result = templates::save_template(template);
         match result {
@@ -107,6 +154,75 @@ impl FrontendInterface {
         barcodes_result
     }
 
+    // Progress and cancellation go through the job when one is given
+    pub fn generate_bulk(
+        &self,
+        config: BulkGeneratorConfig,
+        job: Option<Job>,
+    ) -> anyhow::Result<BulkGenerationResult> {
+        let bulk_generator = BulkGenerator::new(config);
+        match job {
+            Some(job) => bulk_generator.with_job(job).run(),
+            None => bulk_generator.run(),
+        }
+    }
+
+    // Render one page of the layout with the barcodes from the last bulk generation
+    pub fn preview_layout(
+        &self,
+        layout: Layout,
+        barcodes: &[GeneratedBarcode],
+    ) -> anyhow::Result<Vec<u8>> {
+        ExportManager::new().generate_export_preview(layout, barcodes)
+    }
+
+    pub fn layout_report(&self, layout: Layout, barcodes: &[GeneratedBarcode]) -> JsonResponse {
+        match ExportManager::new().packing_report(&layout, barcodes) {
+            Ok(report) => JsonResponse {
+                success: true,
+                message: "Layout report created".to_string(),
+                data: Some(serde_json::to_value(&report).unwrap()),
+            },
+            Err(e) => JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            },
+        }
+    }
+
+    pub fn export_layout(
+        &self,
+        layout: Layout,
+        barcodes: &[GeneratedBarcode],
+        output_path: &str,
+        job: Job,
+    ) -> JsonResponse {
+        let export_manager = ExportManager::new().with_job(job);
+        let result = export_manager.generate_export_pages(layout, barcodes, output_path);
+        match result {
+            Ok(file_paths) => JsonResponse {
+                success: true,
+                message: "Export written successfully".to_string(),
+                data: Some(serde_json::to_value(&file_paths).unwrap()),
+            },
+            Err(e) => JsonResponse {
+                success: false,
+                message: e.to_string(),
+                data: None,
+            },
+        }
+    }
+
+    // Label sheet presets to choose from for the layout
+    pub fn get_label_stocks(&self) -> JsonResponse {
+        JsonResponse {
+            success: true,
+            message: "Label stocks fetched successfully".to_string(),
+            data: Some(serde_json::to_value(label_stock::get_label_stocks()).unwrap()),
+        }
+    }
+
     pub fn get_template(&self, id: String) -> JsonResponse {
         let template_response = templates::get_template(id);
         match template_response {
@@ -124,7 +240,7 @@ impl FrontendInterface {
     }
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct JsonResponse {
     pub success: bool,
     pub message: String,
@@ -1,16 +1,26 @@
 use anyhow::Ok;
 use fontdue::Font;
-use image::{DynamicImage, ImageBuffer, Luma, Rgb, RgbImage};
+use image::{DynamicImage, ImageBuffer, Luma, Pixel, Rgb, Rgba, RgbaImage};
 use std::fs;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
 
-use ab_glyph::{FontArc, PxScale};
+use ab_glyph::PxScale;
 use imageproc::drawing::draw_text_mut;
+use serde::Serialize;
+use zxingcpp::BarcodeFormat;
 
 use crate::generator::{
-    barcode_config::{BarcodeConfigInternal, BarcodeTextStyleConfigInternal, TextPosition},
+    barcode_config::{
+        is_linear, BarcodeColorsInternal, BarcodeConfigInternal, BarcodeTextStyleConfigInternal,
+        HumanReadableConfig, TextAlign, TextPosition,
+    },
     calculator::DimensionCalculator,
+    fonts::{FontRegistry, LoadedFont},
+    hri::{layout_hri, HriAnchor, HriLayout},
     image_editor::ImageEditor,
+    svg_renderer::SvgRenderer,
+    validation::Validator,
 };
 
 pub struct Generator {}
@@ -19,21 +29,60 @@ impl Generator {
     pub fn new() -> Self {
         Self {}
     }
+
     pub fn generate_barcode_svg(
         &self,
         data: &str,
         config: BarcodeConfigInternal,
         filename: &str,
     ) -> anyhow::Result<GeneratedBarcode> {
+        self.generate_barcode_svg_with_dpi(data, config, filename, 300.0)
+    }
+
+    // DPI only decides the raster buffer, the SVG itself is sized in mm
+    pub fn generate_barcode_svg_with_dpi(
+        &self,
+        data: &str,
+        config: BarcodeConfigInternal,
+        filename: &str,
+        dpi: f32,
+    ) -> anyhow::Result<GeneratedBarcode> {
+        let generated_barcode = self.render_barcode(data, &config, filename, dpi)?;
+        self.write_svg(&generated_barcode, dpi)?;
+        Ok(generated_barcode)
+    }
+
+    // Write a rendered barcode to its file_path as SVG
+    pub fn write_svg(&self, generated_barcode: &GeneratedBarcode, dpi: f32) -> anyhow::Result<()> {
+        let svg = SvgRenderer::new().render(generated_barcode, dpi)?;
+        fs::write(&generated_barcode.file_path, svg)?;
+        Ok(())
+    }
+
+    // Write a rendered barcode to its file_path as PNG with the DPI in its metadata
+    pub fn write_png(&self, generated_barcode: &GeneratedBarcode, dpi: f32) -> anyhow::Result<()> {
+        save_image_with_dpi(&generated_barcode.buffer, &generated_barcode.file_path, dpi)
+    }
+
+    // Render symbol at one pixel per module so every pixel maps to a single module
+    pub fn create_symbol(
+        &self,
+        data: &str,
+        config: &BarcodeConfigInternal,
+    ) -> anyhow::Result<BarcodeSymbol> {
         let barcode = zxingcpp::create(config.format)
             .from_str(data)?
-            .to_svg_with(&zxingcpp::write().scale(5))?;
-        fs::write(filename, barcode)?;
-        Ok(GeneratedBarcode {
-            file_path: filename.to_string(),
-            value: data.to_string(),
-            buffer: ImageBuffer::new(0, 0),
-        })
+            .to_image_with(
+                &zxingcpp::write()
+                    .with_quiet_zones(config.quiet_zones)
+                    .scale(1),
+            )?;
+        Ok(BarcodeSymbol::from_luma(
+            config.format,
+            barcode.width() as u32,
+            barcode.height() as u32,
+            &barcode.data(),
+        ))
     }
 
     pub fn generate_barcode_png(
@@ -45,19 +94,16 @@ impl Generator {
         self.generate_barcode_png_with_dpi(data, config, filename, 300.0)
     }
 
-    fn calculate_total_text_height_mm(&self, config: &BarcodeConfigInternal) -> f32 {
-        // Check texts which are upper and lower, and calculate the total height
-        let mut total_height = 0.0;
-        for text in &config.texts {
-            if text.text_position == TextPosition::Upper
-                || text.text_position == TextPosition::Lower
-                || text.text_position == TextPosition::UpperCenter
-                || text.text_position == TextPosition::LowerCenter
-            {
-                total_height += text.text_size as f32 + text.margin as f32;
-            }
-        }
-        total_height
+    // Height of every text block and the human readable line converted from pixels at the render DPI
+    fn calculate_total_text_height_mm(
+        &self,
+        blocks: &[TextBlock],
+        hri: Option<&HriBlock>,
+        dpi: f32,
+    ) -> f32 {
+        let total_height_px: u32 = blocks.iter().map(|block| block.height).sum::<u32>()
+            + hri.map_or(0, |hri| hri.height());
+        DimensionCalculator::new().px_to_mm(total_height_px, dpi)
     }
 
     pub fn generate_barcode_png_with_dpi(
@@ -67,6 +113,172 @@ impl Generator {
         filename: &str,
         dpi: f32,
     ) -> anyhow::Result<GeneratedBarcode> {
+        let generated_barcode = self.render_barcode(data, &config, filename, dpi)?;
+
+        // Save with custom DPI
+        self.write_png(&generated_barcode, dpi)?;
+
+        Ok(generated_barcode)
+    }
+
+    // Compose barcode and texts into an image at the given DPI, shared by every output format.
+    // Nothing is written, filename is only recorded on the result
+    pub fn render_barcode(
+        &self,
+        data: &str,
+        config: &BarcodeConfigInternal,
+        filename: &str,
+        dpi: f32,
+    ) -> anyhow::Result<GeneratedBarcode> {
+        // Catch bad data before zxing, check digits are appended when left out
+        let data = Validator::new().validate(config.format, "data", data)?;
+        let data = data.as_str();
+        let symbol = self.create_symbol(data, config)?;
+        let calculator = DimensionCalculator::new();
+        // With an X-dimension every module gets the same whole number of pixels
+        let pixels_per_module = match config.x_dimension_mm {
+            Some(x_dimension_mm) if x_dimension_mm > 0.0 => {
+                Some(calculator.pixels_per_module(x_dimension_mm, dpi))
+            }
+            Some(x_dimension_mm) => anyhow::bail!("Invalid X-dimension {} mm", x_dimension_mm),
+            None => None,
+        };
+
+        // Texts are laid out first, wrapping needs the width and the barcode gets the height left over
+        let width = match pixels_per_module {
+            Some(pixels_per_module) => symbol.width * pixels_per_module,
+            None => calculator.mm_to_px(config.dimensions.width_mm, dpi).max(1),
+        };
+        let mut text_blocks: Vec<TextBlock> = Vec::new();
+        for text_cfg in &config.texts {
+            if text_cfg.text_position != TextPosition::None {
+                let font = FontRegistry::global().load(&text_cfg.font)?;
+                text_blocks.push(layout_text(text_cfg, font, width));
+            }
+        }
+        let hri = match &config.human_readable {
+            Some(hri_config) => match layout_hri(config.format, data, &symbol) {
+                Some(layout) => Some(HriBlock {
+                    config: hri_config,
+                    font: FontRegistry::global().load(&hri_config.font)?,
+                    layout,
+                }),
+                None => None,
+            },
+            None => None,
+        };
+        let text_height_mm = self.calculate_total_text_height_mm(&text_blocks, hri.as_ref(), dpi);
+        let height_mm = (config.dimensions.height_mm - text_height_mm).max(1.0); // Ensure minimum height of 1mm
+
+        let height = calculator.mm_to_px(height_mm, dpi).max(1);
+
+        let mut final_image = match pixels_per_module {
+            // Linear bars take the height left over, matrix modules stay square
+            Some(pixels_per_module) if is_linear(config.format) => {
+                module_image(&symbol, pixels_per_module, height)
+            }
+            Some(pixels_per_module) => module_image(
+                &symbol,
+                pixels_per_module,
+                symbol.height * pixels_per_module,
+            ),
+            None => self.stretched_image(data, config, width, height)?,
+        };
+        let bar_width_reduction = match &config.bar_width_reduction {
+            Some(reduction) => reduction.to_px(dpi),
+            None => 0.0,
+        };
+        if bar_width_reduction > 0.0 {
+            // Matrix modules shrink on both axes, linear bars only get narrower
+            let both_axes = !is_linear(config.format);
+            let module_width = final_image.width() as f32 / symbol.width.max(1) as f32;
+            let module_height = final_image.height() as f32 / symbol.height.max(1) as f32;
+            let module_size = if both_axes {
+                module_width.min(module_height)
+            } else {
+                module_width
+            };
+            if bar_width_reduction.round() >= module_size.floor() {
+                anyhow::bail!(
+                    "Bar width reduction of {:.1} px leaves nothing of a {:.1} px module",
+                    bar_width_reduction,
+                    module_size
+                );
+            }
+            final_image =
+                reduce_bar_width(&final_image, bar_width_reduction.round() as u32, both_axes);
+        }
+        // Colours are applied after resizing so nearest neighbour scaling keeps the bars crisp
+        let mut final_image = colorize(&final_image, &config.colors, config.transparent_background);
+        let background = background_pixel(config.colors.background, config.transparent_background);
+
+        // Track where the symbol and texts end up, every upper text pushes earlier content down
+        let mut symbol_area = SymbolArea {
+            x: 0,
+            y: 0,
+            width: final_image.width(),
+            height: final_image.height(),
+        };
+        let mut placed_texts: Vec<PlacedText> = Vec::new();
+        let mut guard_extension = None;
+        // The human readable line sits right under the bars, before any other text
+        if let Some(hri) = &hri {
+            let (image, lines, guards) = add_hri_to_image(
+                final_image,
+                hri,
+                &symbol,
+                config.colors.foreground,
+                background,
+            );
+            final_image = image;
+            placed_texts.extend(lines);
+            guard_extension = Some(guards);
+        }
+        for block in &text_blocks {
+            let (image, placement) = add_text_to_image(final_image, block, background);
+            final_image = image;
+            symbol_area.y += placement.content_y;
+            for placed in placed_texts.iter_mut() {
+                placed.y += placement.content_y;
+            }
+            placed_texts.extend(placement.lines);
+        }
+
+        //final_image = image_editor.add_border(final_image, 2, vec![Side::Top, Side::Left]);
+
+        let module_sizing = pixels_per_module.map(|pixels_per_module| ModuleSizing {
+            pixels_per_module,
+            x_dimension_mm: calculator.px_to_mm(pixels_per_module, dpi),
+            symbol_width_mm: calculator.px_to_mm(symbol_area.width, dpi),
+            width_mm: calculator.px_to_mm(final_image.width(), dpi),
+            height_mm: calculator.px_to_mm(final_image.height(), dpi),
+        });
+
+        Ok(GeneratedBarcode {
+            file_path: filename.to_string(),
+            value: data.to_string(),
+            buffer: final_image,
+            symbol,
+            symbol_area,
+            guard_extension,
+            bar_width_reduction,
+            module_sizing,
+            texts: placed_texts,
+            foreground: config.colors.foreground,
+            background: config.colors.background,
+            transparent_background: config.transparent_background,
+        })
+    }
+
+    // zxing output at the configured scale, nearest neighbour resized to width x height.
+    // Bars can end up with uneven widths, an X-dimension avoids that
+    fn stretched_image(
+        &self,
+        data: &str,
+        config: &BarcodeConfigInternal,
+        width: u32,
+        height: u32,
+    ) -> anyhow::Result<ImageBuffer<Luma<u8>, Vec<u8>>> {
         let barcode = zxingcpp::create(config.format)
             .from_str(data)?
             .to_image_with(
@@ -75,39 +287,24 @@ impl Generator {
                     .scale(config.scale),
             )?;
 
-        let width_mm = config.dimensions.width_mm;
-        let height_mm = if config.texts.is_empty() {
-            config.dimensions.height_mm
-        } else {
-            let text_height_mm = self.calculate_total_text_height_mm(&config);
-            (config.dimensions.height_mm - text_height_mm).max(1.0) // Ensure minimum height of 1mm
-        };
-
-        // calculate the height of the added texts in mm, subtract them. otherwise text will be distorted
-
-        let (width, height) = (
-            DimensionCalculator::new().mm_to_px(width_mm, dpi),
-            DimensionCalculator::new().mm_to_px(height_mm, dpi),
-        );
-
-        let width = width.max(1);
-        let height = height.max(1);
-
-        // Debug prints
-        //println!("Barcode data size: {} bytes", barcode.data().len());
-        //println!("Calculated dimensions: {}x{} pixels", width, height);
-        //println!(
-        //    "Expected data size: {} bytes",
-        //    width as usize * height as usize
-        // );
-
         // Use the actual barcode dimensions instead of calculated ones
         let barcode_width = barcode.width() as u32;
-        let barcode_height = barcode.height() as u32;
+        let data = barcode.data();
+        // EAN/UPC guard bars reach below the other bars, those rows are left out so every
+        // output draws the bars the same, the human readable line extends the guards itself
+        let rows = if is_linear(config.format) {
+            linear_bar_rows(&data, barcode_width)
+        } else {
+            0..barcode.height() as usize
+        };
+        let row_bytes = barcode_width as usize;
 
-        let image: ImageBuffer<Luma<u8>, Vec<u8>> =
-            ImageBuffer::from_raw(barcode_width, barcode_height, barcode.data().to_vec())
-                .expect("Failed to create image buffer");
+        let image: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::from_raw(
+            barcode_width,
+            rows.len() as u32,
+            data[rows.start * row_bytes..rows.end * row_bytes].to_vec(),
+        )
+        .expect("Failed to create image buffer");
         let image_editor = ImageEditor::new();
         // Resize image as needed
         let image = if config.dimensions.width_percentage != 100.0 {
@@ -118,141 +315,575 @@ impl Generator {
         let mut final_image = image_editor.resize_dimensions(&image, width, height);
 
         if config.dimensions.height_percentage != 100.0 {
-            final_image =
-                image_editor.resize_height_percentage(&image, config.dimensions.height_percentage);
+            // Resize the sized barcode, the texts were wrapped to its width
+            final_image = image_editor
+                .resize_height_percentage(&final_image, config.dimensions.height_percentage);
         }
+        Ok(final_image)
+    }
+}
 
-        for text_cfg in &config.texts {
-            final_image = add_text_to_luma_image(final_image, &text_cfg.text, text_cfg)?;
+// Rows of zxing output with every bar of a linear symbol, the first row with a dark pixel and
+// the ones repeating it. EAN/UPC continue only their guard bars in the rows below
+fn linear_bar_rows(data: &[u8], width: u32) -> std::ops::Range<usize> {
+    let rows: Vec<&[u8]> = data.chunks(width.max(1) as usize).collect();
+    let Some(first) = rows.iter().position(|row| row.iter().any(|&p| p < 128)) else {
+        return 0..rows.len();
+    };
+    let count = rows[first..]
+        .iter()
+        .take_while(|row| **row == rows[first])
+        .count();
+    first..first + count
+}
+
+// Trim every run of dark pixels by reduction pixels, half on each side with the odd pixel on the
+// right or bottom. Runs are found per row, so it works on stretched and module sized images alike.
+// With both_axes columns are trimmed too and a pixel stays dark when both keep it
+fn reduce_bar_width(
+    image: &ImageBuffer<Luma<u8>, Vec<u8>>,
+    reduction: u32,
+    both_axes: bool,
+) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+    let mut reduced = trim_row_runs(image, reduction);
+    if both_axes {
+        // Rows of the turned image are the columns top to bottom
+        let columns = image::imageops::rotate270(image);
+        let trimmed_columns = image::imageops::rotate90(&trim_row_runs(&columns, reduction));
+        for (pixel, column_pixel) in reduced.pixels_mut().zip(trimmed_columns.pixels()) {
+            pixel[0] = pixel[0].max(column_pixel[0]);
         }
+    }
+    reduced
+}
 
-        //final_image = image_editor.add_border(final_image, 2, vec![Side::Top, Side::Left]);
+fn trim_row_runs(
+    image: &ImageBuffer<Luma<u8>, Vec<u8>>,
+    reduction: u32,
+) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+    let mut trimmed = image.clone();
+    let left_trim = reduction / 2;
+    for y in 0..image.height() {
+        let mut x = 0;
+        while x < image.width() {
+            if image.get_pixel(x, y)[0] >= 128 {
+                x += 1;
+                continue;
+            }
+            let start = x;
+            while x < image.width() && image.get_pixel(x, y)[0] < 128 {
+                x += 1;
+            }
+            let light =
+                (start..start + left_trim).chain(x.saturating_sub(reduction - left_trim)..x);
+            for trim_x in light {
+                trimmed.put_pixel(trim_x, y, Luma([255]));
+            }
+        }
+    }
+    trimmed
+}
 
-        // Save with custom DPI
-        save_image_with_dpi(&final_image, filename, dpi)?;
+// Draw the symbol with whole pixels per module. Linear symbols repeat their row down to height
+fn module_image(
+    symbol: &BarcodeSymbol,
+    pixels_per_module: u32,
+    height: u32,
+) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+    let module_height = (height / symbol.height).max(1);
+    ImageBuffer::from_fn(symbol.width * pixels_per_module, height, |x, y| {
+        let row = (y / module_height).min(symbol.height - 1);
+        if symbol.is_dark(x / pixels_per_module, row) {
+            Luma([0])
+        } else {
+            Luma([255])
+        }
+    })
+}
 
-        Ok(GeneratedBarcode {
-            file_path: filename.to_string(),
-            value: data.to_string(),
-            buffer: image,
+pub fn calculate_text_width(text: &str, font: &Font, font_size: f32) -> f32 {
+    text.chars()
+        .map(|ch| font.metrics(ch, font_size).advance_width)
+        .sum()
+}
+
+// Text with its lines already wrapped, height includes the margin above and below
+struct TextBlock<'a> {
+    style: &'a BarcodeTextStyleConfigInternal,
+    font: Arc<LoadedFont>,
+    lines: Vec<(String, f32)>,
+    height: u32,
+}
+
+// Where the previous content moved on the grown image, and the lines drawn on it
+struct TextPlacement {
+    content_y: u32,
+    lines: Vec<PlacedText>,
+}
+
+// Left and right aligned lines keep this distance to the image edge
+const TEXT_INSET: i32 = 10;
+
+// Split on newlines and wrap words to the width. A single word wider than the width keeps its own line
+fn layout_text(
+    style: &BarcodeTextStyleConfigInternal,
+    font: Arc<LoadedFont>,
+    width: u32,
+) -> TextBlock<'_> {
+    let size = style.text_size as f32;
+    let max_width = (width as i32 - 2 * TEXT_INSET).max(1) as f32;
+    let measure = |text: &str| calculate_text_width(text, &font.metrics, size);
+    let mut lines = Vec::new();
+    for paragraph in style.text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && measure(&candidate) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    let lines: Vec<(String, f32)> = lines
+        .into_iter()
+        .map(|line| {
+            let line_width = measure(&line);
+            (line, line_width)
         })
+        .collect();
+    let line_advance = size * style.line_spacing;
+    let text_height = size + line_advance * (lines.len() - 1) as f32;
+    TextBlock {
+        style,
+        font,
+        lines,
+        height: text_height.ceil() as u32 + 2 * style.margin,
     }
 }
 
-pub fn calculate_text_width(text: &str, font: &Font, font_size: f32) -> f32 {
-    let mut width = 0.0;
-    for ch in text.chars() {
-        let (metrics, _) = font.rasterize(ch, font_size);
-        width += metrics.advance_width;
+// Dark pixels of the zxing output become the foreground, the rest the background
+fn colorize(
+    image: &ImageBuffer<Luma<u8>, Vec<u8>>,
+    colors: &BarcodeColorsInternal,
+    transparent_background: bool,
+) -> RgbaImage {
+    let background = background_pixel(colors.background, transparent_background);
+    RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+        if image.get_pixel(x, y)[0] < 128 {
+            colors.foreground.to_rgba()
+        } else {
+            background
+        }
+    })
+}
+
+// Transparent backgrounds keep their colour so anti-aliased text edges blend towards it
+fn background_pixel(background: Rgb<u8>, transparent: bool) -> Rgba<u8> {
+    let mut pixel = background.to_rgba();
+    if transparent {
+        pixel[3] = 0;
     }
-    width
+    pixel
 }
 
-fn add_text_to_luma_image(
-    luma_img: ImageBuffer<Luma<u8>, Vec<u8>>,
-    text: &str,
-    style: &BarcodeTextStyleConfigInternal,
-) -> anyhow::Result<ImageBuffer<Luma<u8>, Vec<u8>>> {
-    let font_path = format!("./assets/{}.ttf", style.font);
-    let font_bytes = fs::read(&font_path).map_err(|e| {
-        anyhow::anyhow!(
-            "Failed to load font {} with path {} from files: {}",
-            style.font,
-            font_path,
-            e
-        )
-    })?;
-    let cloned_font_bytes = font_bytes.clone();
+// Pixel as printed on white paper, transparent parts show the paper
+pub fn on_white(pixel: &Rgba<u8>) -> Rgb<u8> {
+    let alpha = pixel[3] as u32;
+    let blend = |channel: u8| ((channel as u32 * alpha + 255 * (255 - alpha)) / 255) as u8;
+    Rgb([blend(pixel[0]), blend(pixel[1]), blend(pixel[2])])
+}
 
-    let font = FontArc::try_from_vec(font_bytes).unwrap();
+// Human readable interpretation of a linear symbol, one line of text under the bars
+struct HriBlock<'a> {
+    config: &'a HumanReadableConfig,
+    font: Arc<LoadedFont>,
+    layout: HriLayout,
+}
 
-    let fontcalc = Font::from_bytes(cloned_font_bytes, fontdue::FontSettings::default()).unwrap();
+impl HriBlock<'_> {
+    fn height(&self) -> u32 {
+        self.config.text_size + 2 * self.config.margin
+    }
 
-    let scale = PxScale::from(style.text_size as f32);
-    let text_width = calculate_text_width(&text, &fontcalc, style.text_size as f32);
-    let text_height: u32 = style.text_size + 10; // Add some margin
-    let barcode_width = luma_img.width();
-    let barcode_height = luma_img.height();
+    // Guard bars reach down to about the middle of the digits
+    fn guard_height(&self) -> u32 {
+        self.config.margin + self.config.text_size / 2
+    }
+}
 
-    let rgb_barcode = DynamicImage::ImageLuma8(luma_img).to_rgb8();
+// Grows the image by the HRI line, extends the guard bars into it and draws each digit group
+// at its anchor. Digits outside the bars are clamped to the image when there is no quiet zone
+fn add_hri_to_image(
+    barcode_img: RgbaImage,
+    hri: &HriBlock,
+    symbol: &BarcodeSymbol,
+    foreground: Rgb<u8>,
+    background: Rgba<u8>,
+) -> (RgbaImage, Vec<PlacedText>, GuardExtension) {
+    let width = barcode_img.width();
+    let bars_height = barcode_img.height();
+    let mut final_img = RgbaImage::from_pixel(width, bars_height + hri.height(), background);
+    image::imageops::replace(&mut final_img, &barcode_img, 0, 0);
 
-    let center_x = ((barcode_width as i32 - text_width as i32) / 2).max(0);
+    let module_width = width as f32 / symbol.width.max(1) as f32;
+    let to_px = |module: u32| (module as f32 * module_width).round() as i32;
+    let guards = GuardExtension {
+        modules: hri.layout.guard_modules.clone(),
+        height: hri.guard_height(),
+    };
+    // Continue the bottom row of the bars, so reduced bar widths carry into the extension
+    for &(start, length) in &guards.modules {
+        for x in to_px(start)..to_px(start + length) {
+            let pixel = *barcode_img.get_pixel(x as u32, bars_height - 1);
+            for y in bars_height..bars_height + guards.height {
+                final_img.put_pixel(x as u32, y, pixel);
+            }
+        }
+    }
 
-    let (new_width, new_height, barcode_y, text_x, text_y) = match style.text_position {
-        TextPosition::Upper => (
-            barcode_width,
-            barcode_height + text_height,
-            text_height, // barcode starts after text
-            10,          // text at the very top, left
-            5,
-        ),
-        TextPosition::Lower => (
-            barcode_width,
-            barcode_height + text_height,
-            0,  // barcode at the top
-            10, // text below barcode, left
-            barcode_height + 5,
-        ),
-        TextPosition::UpperCenter => (
-            barcode_width,
-            barcode_height + text_height,
-            text_height,
-            center_x,
-            5,
-        ),
-        TextPosition::LowerCenter => (
-            barcode_width,
-            barcode_height + text_height,
-            0,
-            center_x,
-            barcode_height + 5,
-        ),
-        TextPosition::None => {
-            // No text, just return the barcode as Luma
-            return Ok(DynamicImage::ImageRgb8(rgb_barcode).to_luma8());
+    let size = hri.config.text_size as f32;
+    let text_y = bars_height + hri.config.margin;
+    let mut lines = Vec::new();
+    for group in &hri.layout.groups {
+        let text_width = calculate_text_width(&group.text, &hri.font.metrics, size) as i32;
+        let text_x = match group.anchor {
+            HriAnchor::Between(start, end) => (to_px(start) + to_px(end) - text_width) / 2,
+            HriAnchor::LeftOf(module) => to_px(module) - to_px(1) - text_width,
+            HriAnchor::RightOf(module) => to_px(module) + to_px(1),
         }
+        .clamp(0, (width as i32 - text_width).max(0));
+        draw_text_mut(
+            &mut final_img,
+            foreground.to_rgba(),
+            text_x,
+            text_y as i32,
+            PxScale::from(size),
+            &hri.font.glyphs,
+            &group.text,
+        );
+        lines.push(PlacedText {
+            text: group.text.clone(),
+            font: hri.config.font.clone(),
+            text_size: hri.config.text_size,
+            text_color: foreground,
+            x: text_x,
+            y: text_y,
+        });
+    }
+    (final_img, lines, guards)
+}
+
+fn add_text_to_image(
+    barcode_img: RgbaImage,
+    block: &TextBlock,
+    background: Rgba<u8>,
+) -> (RgbaImage, TextPlacement) {
+    let style = block.style;
+    let barcode_width = barcode_img.width();
+    let barcode_height = barcode_img.height();
+
+    // Upper texts go above the existing content, lower texts below it
+    let (content_y, block_y) = match style.text_position {
+        TextPosition::Upper | TextPosition::UpperCenter => (block.height, 0),
+        _ => (0, barcode_height),
     };
 
-    let mut final_img = RgbImage::new(new_width, new_height as u32);
-    // Fill with white
-    for pixel in final_img.pixels_mut() {
-        *pixel = Rgb([255, 255, 255]);
-    }
+    let mut final_img =
+        RgbaImage::from_pixel(barcode_width, barcode_height + block.height, background);
     // Paste barcode
-    image::imageops::replace(&mut final_img, &rgb_barcode, 0, barcode_y.into());
-    // Draw text
-    draw_text_mut(
-        &mut final_img,
-        style.text_color,
-        text_x,
-        text_y as i32,
-        scale,
-        &font,
-        text,
-    );
-    // Convert back to Luma for further grayscale processing
-    Ok(DynamicImage::ImageRgb8(final_img).to_luma8())
+    image::imageops::replace(&mut final_img, &barcode_img, 0, content_y.into());
+
+    let scale = PxScale::from(style.text_size as f32);
+    let line_advance = style.text_size as f32 * style.line_spacing;
+    let mut lines = Vec::new();
+    for (index, (line, line_width)) in block.lines.iter().enumerate() {
+        let text_x = match style.alignment {
+            TextAlign::Left => TEXT_INSET,
+            TextAlign::Center => (barcode_width as i32 - *line_width as i32) / 2,
+            TextAlign::Right => barcode_width as i32 - *line_width as i32 - TEXT_INSET,
+        }
+        .max(0);
+        let text_y = block_y + style.margin + (line_advance * index as f32).round() as u32;
+        draw_text_mut(
+            &mut final_img,
+            style.text_color.to_rgba(),
+            text_x,
+            text_y as i32,
+            scale,
+            &block.font.glyphs,
+            line,
+        );
+        lines.push(PlacedText {
+            text: line.clone(),
+            font: style.font.clone(),
+            text_size: style.text_size,
+            text_color: style.text_color,
+            x: text_x,
+            y: text_y,
+        });
+    }
+    (final_img, TextPlacement { content_y, lines })
 }
 
 #[derive(Debug, Clone)]
 pub struct GeneratedBarcode {
     pub file_path: String,
     pub value: String,
-    pub buffer: ImageBuffer<Luma<u8>, Vec<u8>>,
+    // Final image with texts, RGBA so colours and transparency survive to the outputs
+    pub buffer: RgbaImage,
+    // Module matrix and its placement inside buffer, used by vector exports
+    pub symbol: BarcodeSymbol,
+    pub symbol_area: SymbolArea,
+    // Guard bars continuing below symbol_area into the human readable line
+    pub guard_extension: Option<GuardExtension>,
+    // Pixels taken off every bar, vector outputs apply it exactly, the raster rounded
+    pub bar_width_reduction: f32,
+    // Printed size when modules were sized from an X-dimension
+    pub module_sizing: Option<ModuleSizing>,
+    pub texts: Vec<PlacedText>,
+    pub foreground: Rgb<u8>,
+    pub background: Rgb<u8>,
+    pub transparent_background: bool,
+}
+
+// Barcode modules, true is a dark module. Linear codes have a single row
+#[derive(Debug, Clone)]
+pub struct BarcodeSymbol {
+    pub width: u32,
+    pub height: u32,
+    pub modules: Vec<bool>,
+}
+
+impl BarcodeSymbol {
+    pub fn from_luma(format: BarcodeFormat, width: u32, height: u32, data: &[u8]) -> Self {
+        let rows: Vec<&[u8]> = data.chunks(width.max(1) as usize).collect();
+        let (height, rows) = if is_linear(format) {
+            (1, vec![rows[linear_bar_rows(data, width).start]])
+        } else {
+            (height, rows)
+        };
+        Self {
+            width,
+            height,
+            modules: rows
+                .iter()
+                .flat_map(|row| row.iter().map(|&p| p < 128))
+                .collect(),
+        }
+    }
+
+    pub fn is_dark(&self, x: u32, y: u32) -> bool {
+        self.modules[(y * self.width + x) as usize]
+    }
+
+    // Vertical run of dark modules through every module as (start row, length), by y * width + x.
+    // Light modules get an empty run
+    pub fn column_runs(&self) -> Vec<(u32, u32)> {
+        let mut runs = vec![(0, 0); self.modules.len()];
+        for x in 0..self.width {
+            let mut y = 0;
+            while y < self.height {
+                if self.is_dark(x, y) {
+                    let start = y;
+                    while y < self.height && self.is_dark(x, y) {
+                        y += 1;
+                    }
+                    for run_y in start..y {
+                        runs[(run_y * self.width + x) as usize] = (start, y - start);
+                    }
+                } else {
+                    y += 1;
+                }
+            }
+        }
+        runs
+    }
+
+    // Horizontal runs of dark modules as (row, start, length), adjacent modules merge into one bar
+    pub fn dark_runs(&self) -> Vec<(u32, u32, u32)> {
+        let mut runs = Vec::new();
+        for y in 0..self.height {
+            let mut x = 0;
+            while x < self.width {
+                if self.is_dark(x, y) {
+                    let start = x;
+                    while x < self.width && self.is_dark(x, y) {
+                        x += 1;
+                    }
+                    runs.push((y, start, x - start));
+                } else {
+                    x += 1;
+                }
+            }
+        }
+        runs
+    }
+}
+
+impl GeneratedBarcode {
+    // Dark areas of the symbol and the guard extension as (left, top, width, height) in buffer
+    // pixels, narrowed by the bar width reduction like the raster. Vector outputs draw these
+    pub fn dark_rects(&self) -> Vec<(f32, f32, f32, f32)> {
+        let symbol = &self.symbol;
+        let area = &self.symbol_area;
+        let module_width = area.width as f32 / symbol.width.max(1) as f32;
+        let module_height = area.height as f32 / symbol.height.max(1) as f32;
+        let reduced = |origin: f32, start: u32, length: u32, module: f32| {
+            let size = length as f32 * module;
+            let reduction = self.bar_width_reduction.min(size);
+            let from = origin + start as f32 * module + reduction / 2.0;
+            (from, from + size - reduction)
+        };
+        let x_span = |start: u32, length: u32| reduced(area.x as f32, start, length, module_width);
+
+        let mut rects = Vec::new();
+        if symbol.height == 1 {
+            for (_, start, length) in symbol.dark_runs() {
+                let (left, right) = x_span(start, length);
+                rects.push((left, area.y as f32, right - left, area.height as f32));
+            }
+        } else {
+            // Every dark module keeps the part inside both its reduced row run and column run,
+            // neighbours at the same height merge into one rectangle
+            let column_runs = symbol.column_runs();
+            for (y, start, length) in symbol.dark_runs() {
+                let (run_left, run_right) = x_span(start, length);
+                // As (left, top, right, bottom) while merging
+                let mut row_rects: Vec<(f32, f32, f32, f32)> = Vec::new();
+                for x in start..start + length {
+                    let cell_left = area.x as f32 + x as f32 * module_width;
+                    let left = cell_left.max(run_left);
+                    let right = (cell_left + module_width).min(run_right);
+                    let (column_start, column_length) =
+                        column_runs[(y * symbol.width + x) as usize];
+                    let (top, bottom) =
+                        reduced(area.y as f32, column_start, column_length, module_height);
+                    let cell_top = area.y as f32 + y as f32 * module_height;
+                    let top = cell_top.max(top);
+                    let bottom = (cell_top + module_height).min(bottom);
+                    if left >= right || top >= bottom {
+                        continue;
+                    }
+                    match row_rects.last_mut() {
+                        Some(last) if last.2 == left && last.1 == top && last.3 == bottom => {
+                            last.2 = right;
+                        }
+                        _ => row_rects.push((left, top, right, bottom)),
+                    }
+                }
+                rects.extend(
+                    row_rects
+                        .into_iter()
+                        .map(|(left, top, right, bottom)| (left, top, right - left, bottom - top)),
+                );
+            }
+        }
+
+        // Whole bars are reduced first and then cut to the guard ranges, so the extension lines
+        // up with the bar above it
+        if let Some(guards) = &self.guard_extension {
+            let top = (area.y + area.height) as f32;
+            for (_, start, length) in symbol.dark_runs() {
+                let (left, right) = x_span(start, length);
+                for &(guard_start, guard_length) in &guards.modules {
+                    let guard_left = area.x as f32 + guard_start as f32 * module_width;
+                    let guard_right = guard_left + guard_length as f32 * module_width;
+                    let (from, to) = (left.max(guard_left), right.min(guard_right));
+                    if from < to {
+                        rects.push((from, top, to - from, guards.height as f32));
+                    }
+                }
+            }
+        }
+        rects
+    }
+}
+
+// Pixel rectangle of the symbol inside GeneratedBarcode::buffer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SymbolArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// Physical size a barcode prints at, the X-dimension is the requested one rounded to whole pixels
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ModuleSizing {
+    pub pixels_per_module: u32,
+    pub x_dimension_mm: f32,
+    // Symbol including quiet zones
+    pub symbol_width_mm: f32,
+    // Whole image including texts
+    pub width_mm: f32,
+    pub height_mm: f32,
+}
+
+// Guard bars of EAN/UPC reach down between the human readable digits. Modules are (start, length)
+// ranges of the symbol row, height is in pixels below the symbol area
+#[derive(Debug, Clone)]
+pub struct GuardExtension {
+    pub modules: Vec<(u32, u32)>,
+    pub height: u32,
+}
+
+// Text drawn on GeneratedBarcode::buffer, x and y are the top left corner in pixels
+#[derive(Debug, Clone)]
+pub struct PlacedText {
+    pub text: String,
+    pub font: String,
+    pub text_size: u32,
+    pub text_color: Rgb<u8>,
+    pub x: i32,
+    pub y: u32,
 }
 
 /// Save an image with custom DPI metadata
-fn save_image_with_dpi(
-    image: &ImageBuffer<Luma<u8>, Vec<u8>>,
-    filename: &str,
-    dpi: f32,
-) -> anyhow::Result<()> {
+fn save_image_with_dpi(image: &RgbaImage, filename: &str, dpi: f32) -> anyhow::Result<()> {
     let file = fs::File::create(filename)?;
-    let ref mut w = BufWriter::new(file);
+    let w = BufWriter::new(file);
 
-    let mut encoder = png::Encoder::new(w, image.width(), image.height());
+    // Alpha is only written when something is see-through, opaque images stay RGB
+    if image.pixels().any(|pixel| pixel[3] < 255) {
+        encode_png_with_dpi(
+            w,
+            image.width(),
+            image.height(),
+            png::ColorType::Rgba,
+            image.as_raw(),
+            dpi,
+        )
+    } else {
+        let rgb_image = DynamicImage::ImageRgba8(image.clone()).to_rgb8();
+        encode_png_with_dpi(
+            w,
+            image.width(),
+            image.height(),
+            png::ColorType::Rgb,
+            rgb_image.as_raw(),
+            dpi,
+        )
+    }
+}
+
+/// Encode 8 bit image data as PNG with the DPI in a pHYs chunk, so it prints at its size
+pub fn encode_png_with_dpi<W: Write>(
+    w: W,
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    data: &[u8],
+    dpi: f32,
+) -> anyhow::Result<()> {
+    let mut encoder = png::Encoder::new(w, width, height);
 
-    // Set DPI metadata using pHYs chunk
     // Convert DPI to pixels per meter (1 inch = 0.0254 meters)
     let pixels_per_meter = (dpi / 0.0254) as u32;
     encoder.set_pixel_dims(Some(png::PixelDimensions {
@@ -260,12 +891,263 @@ fn save_image_with_dpi(
         yppu: pixels_per_meter,
         unit: png::Unit::Meter,
     }));
-
-    encoder.set_color(png::ColorType::Grayscale);
     encoder.set_depth(png::BitDepth::Eight);
-
+    encoder.set_color(color);
     let mut writer = encoder.write_header()?;
-    writer.write_image_data(image.as_raw())?;
-
+    writer.write_image_data(data)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::barcode_config::{
+        BarWidthReduction, BarcodeConfigBuilder, HumanReadableConfig, LengthUnit, ReductionUnit,
+        TextAlign, XDimension,
+    };
+
+    // At 300 DPI 0.33 mm modules round to 4 pixels
+    fn x_dimension_builder(format: BarcodeFormat) -> BarcodeConfigBuilder {
+        let mut builder = BarcodeConfigBuilder::new();
+        builder.set_format(format).set_x_dimension(Some(XDimension {
+            value: 0.33,
+            unit: LengthUnit::Mm,
+        }));
+        builder
+    }
+
+    fn render_with_x_dimension(format: BarcodeFormat, data: &str) -> (GeneratedBarcode, f32) {
+        let builder = x_dimension_builder(format);
+        let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+        let generated = Generator::new()
+            .render_barcode(data, &config, "", 300.0)
+            .unwrap();
+        (generated, config.dimensions.height_mm)
+    }
+
+    #[test]
+    fn test_x_dimension_keeps_linear_height() {
+        let (generated, height_mm) = render_with_x_dimension(BarcodeFormat::EAN13, "400638133393");
+        assert_eq!(generated.buffer.width(), generated.symbol.width * 4);
+        assert_eq!(
+            generated.buffer.height(),
+            DimensionCalculator::new().mm_to_px(height_mm, 300.0)
+        );
+
+        // Matrix modules stay square
+        let (generated, _) = render_with_x_dimension(BarcodeFormat::QRCode, "ABC123");
+        assert_eq!(generated.buffer.height(), generated.symbol.height * 4);
+    }
+
+    #[test]
+    fn test_reduce_bar_width() {
+        // Bars of 4 and 3 pixels on two rows
+        let image = ImageBuffer::from_fn(12, 2, |x, _| {
+            if (1..5).contains(&x) || (7..10).contains(&x) {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+        let reduced = reduce_bar_width(&image, 3, false);
+        for y in 0..2 {
+            let dark: Vec<u32> = (0..12)
+                .filter(|&x| reduced.get_pixel(x, y)[0] < 128)
+                .collect();
+            assert_eq!(dark, vec![2]);
+        }
+
+        // A 4 x 4 module stays square
+        let image = ImageBuffer::from_fn(8, 8, |x, y| {
+            if (2..6).contains(&x) && (2..6).contains(&y) {
+                Luma([0])
+            } else {
+                Luma([255])
+            }
+        });
+        let reduced = reduce_bar_width(&image, 2, true);
+        let dark: Vec<(u32, u32)> = reduced
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[0] < 128)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(dark, vec![(3, 3), (4, 3), (3, 4), (4, 4)]);
+    }
+
+    fn pixel_reduction(value: f32) -> Option<BarWidthReduction> {
+        Some(BarWidthReduction {
+            value,
+            unit: ReductionUnit::Pixels,
+        })
+    }
+
+    #[test]
+    fn test_bar_width_reduction_limits() {
+        let render = |builder: &BarcodeConfigBuilder| {
+            let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+            Generator::new().render_barcode("ABC123", &config, "", 300.0)
+        };
+
+        let mut builder = x_dimension_builder(BarcodeFormat::Code128);
+        builder.set_bar_width_reduction(pixel_reduction(1.0));
+        let generated = render(&builder).unwrap();
+        assert_eq!(generated.bar_width_reduction, 1.0);
+
+        // 4 px modules have nothing left after taking off 4 px
+        builder.set_bar_width_reduction(pixel_reduction(4.0));
+        let error = render(&builder).unwrap_err().to_string();
+        assert!(error.contains("leaves nothing"), "{}", error);
+
+        let mut builder = x_dimension_builder(BarcodeFormat::QRCode);
+        builder.set_bar_width_reduction(pixel_reduction(4.0));
+        assert!(render(&builder).is_err());
+    }
+
+    #[test]
+    fn test_matrix_modules_reduced_on_both_axes() {
+        let mut builder = x_dimension_builder(BarcodeFormat::QRCode);
+        builder.set_bar_width_reduction(pixel_reduction(2.0));
+        let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+        let generated = Generator::new()
+            .render_barcode("ABC123", &config, "", 300.0)
+            .unwrap();
+
+        // The vector rectangles cover exactly the dark raster pixels
+        let rects = generated.dark_rects();
+        let covered: f32 = rects.iter().map(|rect| rect.2 * rect.3).sum();
+        let dark = generated
+            .buffer
+            .pixels()
+            .filter(|pixel| pixel[0] < 128)
+            .count();
+        assert_eq!(covered, dark as f32);
+        for (left, top, width, height) in rects {
+            let pixel = generated
+                .buffer
+                .get_pixel((left + width / 2.0) as u32, (top + height / 2.0) as u32);
+            assert!(pixel[0] < 128);
+        }
+
+        // The top left corner of the finder pattern lost a pixel on both sides
+        let quiet_zone = (0..generated.symbol.width)
+            .find(|&x| generated.symbol.is_dark(x, x))
+            .unwrap();
+        let corner = quiet_zone * 4;
+        assert!(generated.buffer.get_pixel(corner, corner + 2)[0] >= 128);
+        assert!(generated.buffer.get_pixel(corner + 2, corner)[0] >= 128);
+        assert!(generated.buffer.get_pixel(corner + 1, corner + 1)[0] < 128);
+    }
+
+    #[test]
+    fn test_reduced_guards_line_up() {
+        let mut builder = x_dimension_builder(BarcodeFormat::EAN13);
+        builder
+            .set_human_readable(Some(HumanReadableConfig::new()))
+            .set_bar_width_reduction(pixel_reduction(2.0));
+        let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+        let generated = Generator::new()
+            .render_barcode("400638133393", &config, "", 300.0)
+            .unwrap();
+        let area = generated.symbol_area;
+        let extension_top = (area.y + area.height) as f32;
+
+        let rects = generated.dark_rects();
+        let (guards, bars): (Vec<&(f32, f32, f32, f32)>, Vec<_>) =
+            rects.iter().partition(|rect| rect.1 == extension_top);
+        assert_eq!(guards.len(), 6);
+        for guard in guards {
+            assert!(bars.iter().any(|bar| bar.0 == guard.0 && bar.2 == guard.2));
+        }
+
+        // The raster extension continues the reduced bars pixel for pixel
+        let bar_y = area.y + area.height - 1;
+        for x in 0..generated.buffer.width() {
+            if generated.buffer.get_pixel(x, bar_y + 1)[0] < 128 {
+                assert!(generated.buffer.get_pixel(x, bar_y)[0] < 128, "{}", x);
+            }
+        }
+    }
+
+    // 20 px DejaVu Sans with 5 px margins
+    fn text_style(text: &str, line_spacing: f32) -> BarcodeTextStyleConfigInternal {
+        BarcodeTextStyleConfigInternal {
+            text: text.to_string(),
+            text_color: Rgb([0, 0, 0]),
+            text_size: 20,
+            text_position: TextPosition::Lower,
+            font: "DejaVuSans".to_string(),
+            margin: 5,
+            alignment: TextAlign::Center,
+            line_spacing,
+        }
+    }
+
+    fn line_texts(block: &TextBlock) -> Vec<String> {
+        block.lines.iter().map(|(line, _)| line.clone()).collect()
+    }
+
+    #[test]
+    fn test_text_wraps_at_width() {
+        let font = FontRegistry::global().load("DejaVuSans").unwrap();
+        let style = text_style("ABC DEF GHI\nJKL", 1.0);
+        // Room for the first two words between the insets
+        let two_words = calculate_text_width("ABC DEF", &font.metrics, 20.0).ceil() as u32;
+        let block = layout_text(&style, font, two_words + 2 * TEXT_INSET as u32);
+        assert_eq!(line_texts(&block), vec!["ABC DEF", "GHI", "JKL"]);
+        assert!(block
+            .lines
+            .iter()
+            .all(|(_, line_width)| *line_width <= two_words as f32));
+    }
+
+    #[test]
+    fn test_long_word_keeps_own_line() {
+        let font = FontRegistry::global().load("DejaVuSans").unwrap();
+        let style = text_style("AB INTERNATIONALISATION CD", 1.0);
+        let block = layout_text(&style, font, 100);
+        assert_eq!(line_texts(&block), vec!["AB", "INTERNATIONALISATION", "CD"]);
+        assert!(block.lines[1].1 > 100.0 - 2.0 * TEXT_INSET as f32);
+    }
+
+    #[test]
+    fn test_line_spacing_sets_block_height() {
+        let font = FontRegistry::global().load("DejaVuSans").unwrap();
+        let single = text_style("ABC\nDEF", 1.0);
+        let double = text_style("ABC\nDEF", 2.0);
+        // Text size plus one line advance plus both margins
+        assert_eq!(layout_text(&single, font.clone(), 300).height, 20 + 20 + 10);
+        assert_eq!(layout_text(&double, font, 300).height, 20 + 40 + 10);
+    }
+
+    #[test]
+    fn test_colorize_transparent_background() {
+        let image = ImageBuffer::from_fn(2, 1, |x, _| Luma([if x == 0 { 0 } else { 255 }]));
+        let colors = BarcodeColorsInternal {
+            foreground: Rgb([10, 20, 30]),
+            background: Rgb([200, 210, 220]),
+        };
+        let opaque = colorize(&image, &colors, false);
+        assert_eq!(*opaque.get_pixel(0, 0), Rgba([10, 20, 30, 255]));
+        assert_eq!(*opaque.get_pixel(1, 0), Rgba([200, 210, 220, 255]));
+
+        // The background keeps its colour with no alpha, bars stay opaque
+        let transparent = colorize(&image, &colors, true);
+        assert_eq!(*transparent.get_pixel(0, 0), Rgba([10, 20, 30, 255]));
+        assert_eq!(*transparent.get_pixel(1, 0), Rgba([200, 210, 220, 0]));
+    }
+
+    #[test]
+    fn test_linear_bar_rows_skip_quiet_and_guard_rows() {
+        // A blank row, two rows with every bar, then only the guard bars
+        let data = [
+            255, 255, 255, 255, //
+            0, 255, 0, 0, //
+            0, 255, 0, 0, //
+            0, 255, 255, 0, //
+        ];
+        assert_eq!(linear_bar_rows(&data, 4), 1..3);
+        let symbol = BarcodeSymbol::from_luma(BarcodeFormat::EAN13, 4, 4, &data);
+        assert_eq!(symbol.height, 1);
+        assert_eq!(symbol.dark_runs(), vec![(0, 0, 1), (0, 2, 2)]);
+    }
+}
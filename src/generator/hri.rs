@@ -0,0 +1,189 @@
+use zxingcpp::BarcodeFormat;
+
+use crate::generator::{barcode_config::is_linear, generator::BarcodeSymbol};
+
+// Where a group of human readable characters goes, in modules of the symbol row
+#[derive(Debug, Clone, PartialEq)]
+pub enum HriAnchor {
+    // Centered between two module positions
+    Between(u32, u32),
+    // Right edge one module left of the position, for digits printed in the left quiet zone
+    LeftOf(u32),
+    // Left edge one module right of the position, for digits printed in the right quiet zone
+    RightOf(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HriGroup {
+    pub text: String,
+    pub anchor: HriAnchor,
+}
+
+// Human readable interpretation of a linear symbol. Guard modules are (start, length)
+// ranges whose bars extend down between the digit groups
+#[derive(Debug, Clone, PartialEq)]
+pub struct HriLayout {
+    pub groups: Vec<HriGroup>,
+    pub guard_modules: Vec<(u32, u32)>,
+}
+
+// Lays out the HRI of data as GS1 prints it for EAN/UPC, Code39 gets its asterisks and other
+// linear codes are centered under the bars. Matrix codes have no HRI
+pub fn layout_hri(format: BarcodeFormat, data: &str, symbol: &BarcodeSymbol) -> Option<HriLayout> {
+    if !is_linear(format) {
+        return None;
+    }
+    // First and last dark module, the guards are found from there
+    let first = (0..symbol.width).find(|&x| symbol.is_dark(x, 0))?;
+    let last = (0..symbol.width).rev().find(|&x| symbol.is_dark(x, 0))? + 1;
+    let digits: Vec<String> = data.chars().map(|c| c.to_string()).collect();
+    let group = |from: usize, to: usize| digits[from..to].concat();
+    let centered = |text: String, start: u32, end: u32| HriGroup {
+        text,
+        anchor: HriAnchor::Between(first + start, first + end),
+    };
+    let guards = |ranges: &[(u32, u32)]| -> Vec<(u32, u32)> {
+        ranges
+            .iter()
+            .map(|&(start, length)| (first + start, length))
+            .collect()
+    };
+
+    let layout = match format {
+        BarcodeFormat::EAN13 if digits.len() == 13 => HriLayout {
+            groups: vec![
+                HriGroup {
+                    text: group(0, 1),
+                    anchor: HriAnchor::LeftOf(first),
+                },
+                centered(group(1, 7), 3, 45),
+                centered(group(7, 13), 50, 92),
+            ],
+            guard_modules: guards(&[(0, 3), (45, 5), (92, 3)]),
+        },
+        BarcodeFormat::EAN8 if digits.len() == 8 => HriLayout {
+            groups: vec![centered(group(0, 4), 3, 31), centered(group(4, 8), 36, 64)],
+            guard_modules: guards(&[(0, 3), (31, 5), (64, 3)]),
+        },
+        // The first and last digit bars extend with the guards, their digits go outside
+        BarcodeFormat::UPCA if digits.len() == 12 => HriLayout {
+            groups: vec![
+                HriGroup {
+                    text: group(0, 1),
+                    anchor: HriAnchor::LeftOf(first),
+                },
+                centered(group(1, 6), 10, 45),
+                centered(group(6, 11), 50, 85),
+                HriGroup {
+                    text: group(11, 12),
+                    anchor: HriAnchor::RightOf(last),
+                },
+            ],
+            guard_modules: guards(&[(0, 10), (45, 5), (85, 10)]),
+        },
+        BarcodeFormat::UPCE if digits.len() == 8 => HriLayout {
+            groups: vec![
+                HriGroup {
+                    text: group(0, 1),
+                    anchor: HriAnchor::LeftOf(first),
+                },
+                centered(group(1, 7), 3, 45),
+                HriGroup {
+                    text: group(7, 8),
+                    anchor: HriAnchor::RightOf(last),
+                },
+            ],
+            guard_modules: guards(&[(0, 3), (45, 6)]),
+        },
+        BarcodeFormat::Code39 => HriLayout {
+            groups: vec![centered(format!("*{}*", data), 0, last - first)],
+            guard_modules: vec![],
+        },
+        _ => HriLayout {
+            groups: vec![centered(data.to_string(), 0, last - first)],
+            guard_modules: vec![],
+        },
+    };
+    Some(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{
+        barcode_config::{BarcodeConfigBuilder, BarcodeConfigInternal, HumanReadableConfig},
+        generator::Generator,
+    };
+
+    // One row symbol with a 10 module quiet zone around width dark modules
+    fn linear_symbol(width: u32) -> BarcodeSymbol {
+        let mut row = vec![255u8; 10];
+        row.extend(vec![0u8; width as usize]);
+        row.extend(vec![255u8; 10]);
+        BarcodeSymbol::from_luma(BarcodeFormat::Code128, row.len() as u32, 1, &row)
+    }
+
+    #[test]
+    fn test_ean13_groups() {
+        let layout = layout_hri(BarcodeFormat::EAN13, "4006381333931", &linear_symbol(95)).unwrap();
+        assert_eq!(
+            layout.groups,
+            vec![
+                HriGroup {
+                    text: "4".to_string(),
+                    anchor: HriAnchor::LeftOf(10)
+                },
+                HriGroup {
+                    text: "006381".to_string(),
+                    anchor: HriAnchor::Between(13, 55)
+                },
+                HriGroup {
+                    text: "333931".to_string(),
+                    anchor: HriAnchor::Between(60, 102)
+                },
+            ]
+        );
+        assert_eq!(layout.guard_modules, vec![(10, 3), (55, 5), (102, 3)]);
+    }
+
+    #[test]
+    fn test_code39_asterisks() {
+        let layout = layout_hri(BarcodeFormat::Code39, "ABC", &linear_symbol(40)).unwrap();
+        assert_eq!(layout.groups[0].text, "*ABC*");
+        assert_eq!(layout.groups[0].anchor, HriAnchor::Between(10, 50));
+        assert!(layout.guard_modules.is_empty());
+    }
+
+    #[test]
+    fn test_rendered_ean_upc_get_hri() {
+        // zxing draws the guard bars longer, the symbol still has to come out as one bar row
+        let cases = [
+            (
+                BarcodeFormat::EAN13,
+                "400638133393",
+                vec!["4", "006381", "333931"],
+            ),
+            (BarcodeFormat::EAN8, "9638507", vec!["9638", "5074"]),
+            (
+                BarcodeFormat::UPCA,
+                "03600029145",
+                vec!["0", "36000", "29145", "2"],
+            ),
+            (BarcodeFormat::UPCE, "0425261", vec!["0", "425261", "4"]),
+        ];
+        for (format, data, groups) in cases {
+            let mut builder = BarcodeConfigBuilder::new();
+            builder
+                .set_format(format)
+                .set_human_readable(Some(HumanReadableConfig::new()));
+            let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+            let generated = Generator::new()
+                .render_barcode(data, &config, "", 300.0)
+                .unwrap();
+            assert_eq!(generated.symbol.height, 1, "{}", format);
+            let texts: Vec<&str> = generated.texts.iter().map(|t| t.text.as_str()).collect();
+            assert_eq!(texts, groups, "{}", format);
+            assert!(generated.guard_extension.is_some(), "{}", format);
+        }
+    }
+}
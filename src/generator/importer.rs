@@ -26,7 +26,7 @@ impl Importer {
     // Read CSV file and return barcodes. Later add rest of the configuration data.
     // Read: Value
     pub fn import_from_csv(&self, file_path: &str) -> anyhow::Result<Vec<BarcodeImportRowCSV>> {
-        let file = File::open(file_path).unwrap();
+        let file = File::open(file_path)?;
         let mut rdr = csv::Reader::from_reader(file);
         let mut barcodes = Vec::new();
         for result in rdr.deserialize() {
@@ -39,13 +39,20 @@ impl Importer {
     }
 }
 
+// Empty or missing columns keep the value from the template used for bulk generation
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct BarcodeImportRowCSV {
     pub value: String,
+    #[serde(default)]
     pub upper_center_text: String,
+    #[serde(default)]
     pub lower_center_text: String,
-    pub scale: i32,
-    pub height_percentage: f32,
-    pub width_percentage: f32,
-    pub font_size: u32,
+    #[serde(default)]
+    pub scale: Option<i32>,
+    #[serde(default)]
+    pub height_percentage: Option<f32>,
+    #[serde(default)]
+    pub width_percentage: Option<f32>,
+    #[serde(default)]
+    pub font_size: Option<u32>,
 }
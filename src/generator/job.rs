@@ -0,0 +1,144 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use serde::Serialize;
+use uuid::Uuid;
+
+// Snapshot sent to the progress callback every time a row or page is done
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct JobProgress {
+    pub job_id: String,
+    pub rows_total: usize,
+    pub rows_done: usize,
+    pub rows_failed: usize,
+    // 1 based page being written, 0 until the export starts
+    pub current_page: usize,
+}
+
+// Returned as the error of a job which was cancelled, so callers can tell it apart from failures
+#[derive(Debug)]
+pub struct JobCancelled {
+    pub job_id: String,
+}
+
+impl std::fmt::Display for JobCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Job {} was cancelled", self.job_id)
+    }
+}
+
+impl std::error::Error for JobCancelled {}
+
+// Handle to a running bulk or export job. Clones share the cancel flag and progress,
+// so the starter keeps one to cancel while the worker reports through another
+#[derive(Clone)]
+pub struct Job {
+    pub id: String,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<JobProgress>>,
+    on_progress: Arc<dyn Fn(&JobProgress) + Send + Sync>,
+}
+
+impl Job {
+    pub fn new() -> Self {
+        let id = Uuid::new_v4().to_string();
+        Self {
+            progress: Arc::new(Mutex::new(JobProgress {
+                job_id: id.clone(),
+                ..Default::default()
+            })),
+            id,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            on_progress: Arc::new(|_| {}),
+        }
+    }
+
+    pub fn on_progress(self, on_progress: impl Fn(&JobProgress) + Send + Sync + 'static) -> Self {
+        Self {
+            on_progress: Arc::new(on_progress),
+            ..self
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Called between rows and pages, stops the job with JobCancelled once cancel was requested
+    pub fn check_cancelled(&self) -> anyhow::Result<()> {
+        if self.is_cancelled() {
+            return Err(JobCancelled {
+                job_id: self.id.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn progress(&self) -> JobProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
+    pub fn set_rows_total(&self, rows_total: usize) {
+        self.update(|progress| progress.rows_total = rows_total);
+    }
+
+    pub fn row_done(&self) {
+        self.update(|progress| progress.rows_done += 1);
+    }
+
+    pub fn rows_failed(&self, count: usize) {
+        if count > 0 {
+            self.update(|progress| progress.rows_failed += count);
+        }
+    }
+
+    pub fn page_started(&self, page: usize) {
+        self.update(|progress| progress.current_page = page);
+    }
+
+    fn update(&self, change: impl FnOnce(&mut JobProgress)) {
+        let snapshot = {
+            let mut progress = self.progress.lock().unwrap();
+            change(&mut progress);
+            progress.clone()
+        };
+        (self.on_progress)(&snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_and_cancel() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let sink = reported.clone();
+        let job =
+            Job::new().on_progress(move |progress| sink.lock().unwrap().push(progress.clone()));
+        job.set_rows_total(3);
+        job.row_done();
+        job.rows_failed(1);
+        job.page_started(1);
+
+        let last = reported.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(last.job_id, job.id);
+        assert_eq!(
+            (last.rows_total, last.rows_done, last.rows_failed),
+            (3, 1, 1)
+        );
+        assert_eq!(last.current_page, 1);
+
+        assert!(job.check_cancelled().is_ok());
+        job.clone().cancel();
+        let error = job.check_cancelled().unwrap_err();
+        assert!(error.downcast_ref::<JobCancelled>().is_some());
+    }
+}
@@ -1,88 +1,298 @@
-use std::{fs::File, io::Write};
+use std::{fs::File, io::Write, path::Path};
 
 use serde::{Deserialize, Serialize};
 
 use crate::generator::{
-    barcode_config::BarcodeConfig,
-    exporting::{paper::Paper, pdf_exporter::PdfExporter, png_exporter::PngExporter},
+    barcode_config::{BarcodeConfig, BarcodeConfigBuilder},
+    exporting::{
+        label_stock::LabelStock,
+        packing::{self, Packing, PackingReport},
+        paper::{self, Orientation, Paper},
+        pdf_exporter::PdfExporter,
+        png_exporter::PngExporter,
+    },
     generator::GeneratedBarcode,
+    job::Job,
 };
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub enum ExportFile {
     PDF,
     PNG,
 }
 
-#[derive(Serialize, Deserialize)]
+// Where the block of cells sits in the space left between the margins
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Alignment {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Margins {
+    pub top_mm: f32,
+    pub right_mm: f32,
+    pub bottom_mm: f32,
+    pub left_mm: f32,
+}
+
+impl Margins {
+    // Most printers can't print the outer few millimetres of the paper
+    pub fn new() -> Self {
+        Self::uniform(5.0)
+    }
+
+    pub fn uniform(mm: f32) -> Self {
+        Self {
+            top_mm: mm,
+            right_mm: mm,
+            bottom_mm: mm,
+            left_mm: mm,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Layout {
     pub config: BarcodeConfig,
     pub max_rows: u32,
     pub max_cols: u32,
     pub paper: Paper,
+    #[serde(default)]
+    pub orientation: Orientation,
+    // Resolution of the page pixels, the barcodes should be rendered at the same DPI
+    #[serde(default = "Layout::default_dpi")]
+    pub dpi: f32,
+    // Centers as many cells as fit on the whole page, margins and alignment are then ignored
     pub auto_margin: bool,
+    #[serde(default = "Margins::new")]
+    pub margins: Margins,
+    // Space between neighbouring columns and rows
+    #[serde(default)]
+    pub gutter_x_mm: f32,
+    #[serde(default)]
+    pub gutter_y_mm: f32,
+    #[serde(default)]
+    pub horizontal_alignment: Alignment,
+    #[serde(default)]
+    pub vertical_alignment: Alignment,
+    // Grid packing honours max_rows, max_cols and alignment, shelf packing doesn't
+    #[serde(default)]
+    pub packing: Packing,
+    // Cells of the first page left empty, for sheets with labels already used. Cells are counted
+    // from 0 row by row. Shelf packing has no cells, so both must be left at their defaults
+    #[serde(default)]
+    pub start_cell: u32,
+    #[serde(default)]
+    pub skipped_cells: Vec<u32>,
     pub grid_borders: bool,
     pub export_file: ExportFile,
+    // Place barcodes on the labels of a pre-cut sheet, paper and packing settings are then ignored
+    #[serde(default)]
+    pub label_stock: Option<LabelStock>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self {
+            config: BarcodeConfigBuilder::new().build(),
+            // 0 places as many barcodes as fit on the paper
+            max_rows: 0,
+            max_cols: 0,
+            paper: Paper::A4,
+            orientation: Orientation::Portrait,
+            dpi: Self::default_dpi(),
+            auto_margin: true,
+            margins: Margins::new(),
+            gutter_x_mm: 0.0,
+            gutter_y_mm: 0.0,
+            horizontal_alignment: Alignment::Start,
+            vertical_alignment: Alignment::Start,
+            packing: Packing::Grid,
+            start_cell: 0,
+            skipped_cells: Vec::new(),
+            grid_borders: false,
+            export_file: ExportFile::PDF,
+            label_stock: None,
+        }
+    }
+
+    pub fn page_dimensions_mm(&self) -> (f32, f32) {
+        match &self.label_stock {
+            Some(stock) => paper::get_paper_dimensions_mm(&stock.paper, stock.orientation),
+            None => paper::get_paper_dimensions_mm(&self.paper, self.orientation),
+        }
+    }
+
+    pub fn page_dimensions_px(&self) -> (u32, u32) {
+        match &self.label_stock {
+            Some(stock) => {
+                paper::get_paper_dimensions_px(&stock.paper, stock.orientation, self.dpi)
+            }
+            None => paper::get_paper_dimensions_px(&self.paper, self.orientation, self.dpi),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.dpi.is_nan() || self.dpi <= 0.0 {
+            anyhow::bail!("DPI must be larger than 0, got {}", self.dpi);
+        }
+        if let Some(stock) = &self.label_stock {
+            return stock.validate();
+        }
+        if self.packing == Packing::Shelf
+            && (self.start_cell != 0 || !self.skipped_cells.is_empty())
+        {
+            anyhow::bail!("Start cell and skipped cells need grid packing or a label stock");
+        }
+        self.paper.validate()?;
+        let margins = &self.margins;
+        let spacings = [
+            margins.top_mm,
+            margins.right_mm,
+            margins.bottom_mm,
+            margins.left_mm,
+            self.gutter_x_mm,
+            self.gutter_y_mm,
+        ];
+        if spacings.iter().any(|mm| mm.is_nan() || *mm < 0.0) {
+            anyhow::bail!("Margins and gutters can't be negative");
+        }
+        let (width_mm, height_mm) = self.page_dimensions_mm();
+        if margins.left_mm + margins.right_mm >= width_mm
+            || margins.top_mm + margins.bottom_mm >= height_mm
+        {
+            anyhow::bail!("Margins leave no space on the paper");
+        }
+        Ok(())
+    }
+
+    fn default_dpi() -> f32 {
+        300.0
+    }
 }
 
-pub struct ExportManager {}
+pub struct ExportManager {
+    job: Option<Job>,
+}
 
 // Handles placing barcodes on different papers
 impl ExportManager {
     pub fn new() -> Self {
-        Self {}
+        Self { job: None }
+    }
+
+    // Report the page being written and stop between pages when the job is cancelled
+    pub fn with_job(self, job: Job) -> Self {
+        Self { job: Some(job) }
     }
 
+    // Write the export next to output_path, one file for PDF and numbered files for PNG pages.
+    // Returns the paths of the written files
     pub fn generate_export_pages(
         &self,
         layout: Layout,
-        barcodes: Vec<GeneratedBarcode>,
-    ) -> anyhow::Result<()> {
+        barcodes: &[GeneratedBarcode],
+        output_path: &str,
+    ) -> anyhow::Result<Vec<String>> {
+        layout.validate()?;
+        let output_stem = Path::new(output_path).with_extension("");
+        let output_stem = output_stem.to_string_lossy();
         match layout.export_file {
             ExportFile::PDF => {
-                let pdf_exporter = PdfExporter::new();
-                Ok(())
+                let pdf_exporter = PdfExporter::new(layout).with_job(self.job.clone());
+                let document = pdf_exporter.create_full_export(barcodes)?;
+                let file_path = format!("{}.pdf", output_stem);
+                let mut file = File::create(&file_path)?;
+                file.write_all(&document)?;
+                Ok(vec![file_path])
             }
             ExportFile::PNG => {
-                let png_exporter = PngExporter::new(layout);
-                let result = png_exporter.create_full_export(barcodes.as_slice());
-                match result {
-                    Ok(result) => {
-                        // Temp solution - save images to
-                        let mut index = 1;
-                        for export in result {
-                            let mut temp_file =
-                                File::create(format!("/temp/export_{}.png", index))?;
-                            temp_file.write_all(&export.page);
-                            index += 1;
-                        }
-                        Ok(())
-                    }
-                    Err(e) => Err(e),
+                let png_exporter = PngExporter::new(layout).with_job(self.job.clone());
+                let result = png_exporter.create_full_export(barcodes)?;
+                let mut file_paths = Vec::new();
+                for (index, export) in result.iter().enumerate() {
+                    let file_path = format!("{}_{}.png", output_stem, index + 1);
+                    let mut file = File::create(&file_path)?;
+                    file.write_all(&export.page)?;
+                    file_paths.push(file_path);
                 }
+                Ok(file_paths)
             }
         }
     }
 
+    // Pages the layout needs for the barcodes and how much of each page they cover
+    pub fn packing_report(
+        &self,
+        layout: &Layout,
+        barcodes: &[GeneratedBarcode],
+    ) -> anyhow::Result<PackingReport> {
+        layout.validate()?;
+        let pages = packing::place_barcodes(layout, barcodes)?;
+        Ok(packing::packing_report(layout, &pages))
+    }
+
     // Create preview of one page to send back to frontend
     pub fn generate_export_preview(
         &self,
         layout: Layout,
-        barcodes: Vec<GeneratedBarcode>,
+        barcodes: &[GeneratedBarcode],
     ) -> anyhow::Result<Vec<u8>> {
+        layout.validate()?;
         match layout.export_file {
             ExportFile::PNG => {
                 let png_exporter = PngExporter::new(layout);
-                let result = png_exporter.create_export_preview(barcodes.as_slice());
+                let result = png_exporter.create_export_preview(barcodes);
                 match result {
                     Ok(r) => Ok(r.page),
                     Err(e) => Err(e),
                 }
             }
             ExportFile::PDF => {
-                let pdf_exporter = PdfExporter::new();
-                pdf_exporter.create_export_preview()
+                let pdf_exporter = PdfExporter::new(layout);
+                pdf_exporter.create_export_preview(barcodes)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::exporting::label_stock;
+
+    #[test]
+    fn test_dpi_must_be_positive() {
+        let mut layout = Layout::new();
+        assert!(layout.validate().is_ok());
+        for dpi in [0.0, -300.0, f32::NAN] {
+            layout.dpi = dpi;
+            assert!(layout.validate().is_err(), "{}", dpi);
+        }
+        layout.label_stock = Some(label_stock::get_label_stock("avery-l7160").unwrap());
+        assert!(layout.validate().is_err());
+    }
+
+    #[test]
+    fn test_shelf_packing_rejects_cell_offsets() {
+        let mut layout = Layout::new();
+        layout.start_cell = 3;
+        layout.skipped_cells = vec![5];
+        assert!(layout.validate().is_ok());
+
+        layout.packing = Packing::Shelf;
+        assert!(layout.validate().is_err());
+        layout.start_cell = 0;
+        assert!(layout.validate().is_err());
+        layout.skipped_cells.clear();
+        assert!(layout.validate().is_ok());
+
+        // Label stocks place on their own cells whatever the packing
+        layout.start_cell = 3;
+        layout.label_stock = Some(label_stock::get_label_stock("avery-l7160").unwrap());
+        assert!(layout.validate().is_ok());
+    }
+}
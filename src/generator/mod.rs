@@ -5,9 +5,16 @@ pub mod calculator;
 pub mod database;
 pub mod exporter;
 pub mod exporting;
+pub mod fonts;
 pub mod frontend_interface;
 pub mod generator;
+pub mod hri;
 pub mod image_editor;
 pub mod importer;
+pub mod job;
 pub mod layout;
+pub mod quality;
+pub mod svg_renderer;
 pub mod templates;
+pub mod validation;
+pub mod verification;
@@ -0,0 +1,421 @@
+use serde::{Deserialize, Serialize};
+use zxingcpp::BarcodeFormat;
+
+use crate::generator::{
+    barcode_config::is_linear,
+    generator::{on_white, GeneratedBarcode},
+};
+
+// Scan lines across the bar height, ISO/IEC 15416 asks for ten
+const SCAN_COUNT: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Grade {
+    F,
+    D,
+    C,
+    B,
+    A,
+}
+
+impl Grade {
+    fn value(self) -> f32 {
+        self as u8 as f32
+    }
+
+    // Average grade values are rounded like the standard does for the overall symbol grade
+    fn from_value(value: f32) -> Self {
+        match value {
+            v if v >= 3.5 => Grade::A,
+            v if v >= 2.5 => Grade::B,
+            v if v >= 1.5 => Grade::C,
+            v if v >= 0.5 => Grade::D,
+            _ => Grade::F,
+        }
+    }
+
+    fn pass_fail(pass: bool) -> Self {
+        if pass {
+            Grade::A
+        } else {
+            Grade::F
+        }
+    }
+
+    // Thresholds from A to D, higher values are better
+    fn at_least(value: f32, thresholds: [f32; 4]) -> Self {
+        [Grade::A, Grade::B, Grade::C, Grade::D]
+            .into_iter()
+            .zip(thresholds)
+            .find(|(_, threshold)| value >= *threshold)
+            .map_or(Grade::F, |(grade, _)| grade)
+    }
+
+    // Thresholds from A to D, lower values are better
+    fn at_most(value: f32, thresholds: [f32; 4]) -> Self {
+        [Grade::A, Grade::B, Grade::C, Grade::D]
+            .into_iter()
+            .zip(thresholds)
+            .find(|(_, threshold)| value <= *threshold)
+            .map_or(Grade::F, |(grade, _)| grade)
+    }
+}
+
+impl std::fmt::Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Measurements of one scan line. Reflectances are percentages, widths are in modules
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanReport {
+    pub symbol_contrast: f32,
+    pub min_reflectance: f32,
+    pub min_edge_contrast: f32,
+    pub modulation: f32,
+    pub defects: f32,
+    pub decodability: f32,
+    pub edges_found: usize,
+    pub edges_expected: usize,
+    pub quiet_zone_left: f32,
+    pub quiet_zone_right: f32,
+    pub quiet_zone_required: f32,
+    pub grades: ParameterGrades,
+    // Lowest of the parameter grades
+    pub grade: Grade,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterGrades {
+    pub symbol_contrast: Grade,
+    pub min_reflectance: Grade,
+    pub min_edge_contrast: Grade,
+    pub modulation: Grade,
+    pub defects: Grade,
+    pub decodability: Grade,
+    pub edge_determination: Grade,
+    pub quiet_zone: Grade,
+}
+
+impl ParameterGrades {
+    fn lowest(&self) -> Grade {
+        [
+            self.symbol_contrast,
+            self.min_reflectance,
+            self.min_edge_contrast,
+            self.modulation,
+            self.defects,
+            self.decodability,
+            self.edge_determination,
+            self.quiet_zone,
+        ]
+        .into_iter()
+        .min()
+        .unwrap_or(Grade::F)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityReport {
+    pub scans: Vec<ScanReport>,
+    // Average of the scan grades
+    pub grade: Grade,
+}
+
+// Estimates an ISO/IEC 15416 print quality grade from the rendered image of a linear barcode.
+// Reflectance is taken from the red channel on white paper, as scanners read with red light.
+// It grades the image as rendered, the printer will only make it worse
+pub struct QualityGrader {}
+
+impl QualityGrader {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn grade(
+        &self,
+        barcode: &GeneratedBarcode,
+        format: BarcodeFormat,
+    ) -> anyhow::Result<QualityReport> {
+        if !is_linear(format) {
+            anyhow::bail!("Print quality grading is only available for linear barcodes");
+        }
+        let area = &barcode.symbol_area;
+        let scans: Vec<ScanReport> = (0..SCAN_COUNT)
+            .map(|scan| {
+                // Evenly spaced over the bar height, away from the top and bottom edge
+                let y = area.y + area.height * (2 * scan + 1) / (2 * SCAN_COUNT);
+                let profile: Vec<f32> = (0..barcode.buffer.width())
+                    .map(|x| reflectance(barcode, x, y))
+                    .collect();
+                self.grade_scan(barcode, format, &profile)
+            })
+            .collect();
+        let average = scans.iter().map(|scan| scan.grade.value()).sum::<f32>() / scans.len() as f32;
+        Ok(QualityReport {
+            scans,
+            grade: Grade::from_value(average),
+        })
+    }
+
+    fn grade_scan(
+        &self,
+        barcode: &GeneratedBarcode,
+        format: BarcodeFormat,
+        profile: &[f32],
+    ) -> ScanReport {
+        let max_reflectance = profile.iter().cloned().fold(0.0, f32::max);
+        let min_reflectance = profile.iter().cloned().fold(100.0, f32::min);
+        let symbol_contrast = max_reflectance - min_reflectance;
+        let threshold = min_reflectance + symbol_contrast / 2.0;
+        let module_width = barcode.symbol_area.width as f32 / barcode.symbol.width.max(1) as f32;
+
+        // Elements between the first and the last bar, as (is_bar, start, end) pixel ranges
+        let mut elements: Vec<(bool, usize, usize)> = Vec::new();
+        for (x, &value) in profile.iter().enumerate() {
+            let is_bar = value < threshold;
+            match elements.last_mut() {
+                Some((last_is_bar, _, end)) if *last_is_bar == is_bar => *end = x + 1,
+                _ => elements.push((is_bar, x, x + 1)),
+            }
+        }
+        let quiet_zone_left = match elements.first() {
+            Some((false, _, end)) => *end as f32 / module_width,
+            _ => 0.0,
+        };
+        let quiet_zone_right = match elements.last() {
+            Some((false, start, end)) => (end - start) as f32 / module_width,
+            _ => 0.0,
+        };
+        let first_bar = elements.iter().position(|(is_bar, ..)| *is_bar);
+        let last_bar = elements.iter().rposition(|(is_bar, ..)| *is_bar);
+        let elements = match (first_bar, last_bar) {
+            (Some(first), Some(last)) => &elements[first..=last],
+            _ => &elements[0..0],
+        };
+
+        // Reflectance extremes per element give the edge contrast and the defects
+        let element_extremes: Vec<(f32, f32)> = elements
+            .iter()
+            .map(|(_, start, end)| {
+                let values = &profile[*start..*end];
+                (
+                    values.iter().cloned().fold(100.0, f32::min),
+                    values.iter().cloned().fold(0.0, f32::max),
+                )
+            })
+            .collect();
+        let min_edge_contrast = element_extremes
+            .windows(2)
+            .zip(elements)
+            .map(|(pair, (is_bar, ..))| {
+                let (bar, space) = if *is_bar {
+                    (pair[0], pair[1])
+                } else {
+                    (pair[1], pair[0])
+                };
+                space.1 - bar.0
+            })
+            .fold(symbol_contrast, f32::min);
+        let modulation = if symbol_contrast > 0.0 {
+            min_edge_contrast / symbol_contrast
+        } else {
+            0.0
+        };
+        let max_non_uniformity = element_extremes
+            .iter()
+            .map(|(min, max)| max - min)
+            .fold(0.0, f32::max);
+        let defects = if symbol_contrast > 0.0 {
+            max_non_uniformity / symbol_contrast
+        } else {
+            1.0
+        };
+
+        // Element widths against the module runs of the symbol, bars are expected narrower by the reduction
+        let expected = expected_elements(barcode, module_width);
+        let edges_found = edge_count(elements.len());
+        let edges_expected = edge_count(expected.len());
+        let decodability = if edges_found == edges_expected && !expected.is_empty() {
+            let max_deviation = elements
+                .iter()
+                .zip(&expected)
+                .map(|((_, start, end), expected)| ((end - start) as f32 - expected).abs())
+                .fold(0.0, f32::max);
+            (1.0 - max_deviation / module_width).max(0.0)
+        } else {
+            0.0
+        };
+
+        let quiet_zone_required = required_quiet_zone(format);
+        let grades = ParameterGrades {
+            symbol_contrast: Grade::at_least(symbol_contrast, [70.0, 55.0, 40.0, 20.0]),
+            min_reflectance: Grade::pass_fail(min_reflectance <= max_reflectance / 2.0),
+            min_edge_contrast: Grade::pass_fail(min_edge_contrast >= 15.0),
+            modulation: Grade::at_least(modulation, [0.70, 0.60, 0.50, 0.40]),
+            defects: Grade::at_most(defects, [0.15, 0.20, 0.25, 0.30]),
+            decodability: Grade::at_least(decodability, [0.62, 0.50, 0.37, 0.25]),
+            edge_determination: Grade::pass_fail(edges_found == edges_expected),
+            // Stretched images may lose a pixel of the quiet zone to rounding
+            quiet_zone: Grade::pass_fail(
+                quiet_zone_left.min(quiet_zone_right) + 1.0 / module_width >= quiet_zone_required,
+            ),
+        };
+        ScanReport {
+            symbol_contrast,
+            min_reflectance,
+            min_edge_contrast,
+            modulation,
+            defects,
+            decodability,
+            edges_found,
+            edges_expected,
+            quiet_zone_left,
+            quiet_zone_right,
+            quiet_zone_required,
+            grade: grades.lowest(),
+            grades,
+        }
+    }
+}
+
+// Every element has an edge on its left, the last bar one on its right too
+fn edge_count(elements: usize) -> usize {
+    if elements == 0 {
+        0
+    } else {
+        elements + 1
+    }
+}
+
+// Red channel reflectance in percent, transparent pixels show the white paper
+fn reflectance(barcode: &GeneratedBarcode, x: u32, y: u32) -> f32 {
+    on_white(barcode.buffer.get_pixel(x, y))[0] as f32 / 255.0 * 100.0
+}
+
+// Pixel width of every bar and space between the first and last bar of the symbol row
+fn expected_elements(barcode: &GeneratedBarcode, module_width: f32) -> Vec<f32> {
+    let symbol = &barcode.symbol;
+    let mut widths: Vec<(bool, u32)> = Vec::new();
+    for x in 0..symbol.width {
+        let is_bar = symbol.is_dark(x, 0);
+        match widths.last_mut() {
+            Some((last_is_bar, count)) if *last_is_bar == is_bar => *count += 1,
+            _ => widths.push((is_bar, 1)),
+        }
+    }
+    let first_bar = widths.iter().position(|(is_bar, _)| *is_bar);
+    let last_bar = widths.iter().rposition(|(is_bar, _)| *is_bar);
+    let widths = match (first_bar, last_bar) {
+        (Some(first), Some(last)) => &widths[first..=last],
+        _ => &widths[0..0],
+    };
+    let reduction = barcode.bar_width_reduction.round();
+    widths
+        .iter()
+        .map(|(is_bar, count)| {
+            let width = *count as f32 * module_width;
+            if *is_bar {
+                width - reduction
+            } else {
+                width + reduction
+            }
+        })
+        .collect()
+}
+
+// Light margin each side needs in modules, EAN/UPC use the smaller right side of their spec
+fn required_quiet_zone(format: BarcodeFormat) -> f32 {
+    match format {
+        BarcodeFormat::EAN13 => 7.0,
+        BarcodeFormat::EAN8 => 7.0,
+        BarcodeFormat::UPCA => 9.0,
+        BarcodeFormat::UPCE => 7.0,
+        _ => 10.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{
+        barcode_config::{BarcodeConfigBuilder, BarcodeConfigInternal},
+        generator::{BarcodeSymbol, Generator, SymbolArea},
+    };
+    use image::{Rgb, RgbaImage};
+
+    // 3 px modules, 10 module quiet zones, bars drawn in the given grey
+    fn barcode(row: &[u8], bar_grey: u8) -> GeneratedBarcode {
+        let mut modules = vec![255u8; 10];
+        modules.extend_from_slice(row);
+        modules.extend(vec![255u8; 10]);
+        let symbol =
+            BarcodeSymbol::from_luma(BarcodeFormat::Code128, modules.len() as u32, 1, &modules);
+        let buffer = RgbaImage::from_fn(symbol.width * 3, 20, |x, _| {
+            if symbol.is_dark(x / 3, 0) {
+                image::Rgba([bar_grey, bar_grey, bar_grey, 255])
+            } else {
+                image::Rgba([255, 255, 255, 255])
+            }
+        });
+        GeneratedBarcode {
+            file_path: String::new(),
+            value: String::new(),
+            symbol_area: SymbolArea {
+                x: 0,
+                y: 0,
+                width: buffer.width(),
+                height: buffer.height(),
+            },
+            buffer,
+            symbol,
+            guard_extension: None,
+            bar_width_reduction: 0.0,
+            module_sizing: None,
+            texts: vec![],
+            foreground: Rgb([0, 0, 0]),
+            background: Rgb([255, 255, 255]),
+            transparent_background: false,
+        }
+    }
+
+    #[test]
+    fn test_clean_symbol_grades_a() {
+        let report = QualityGrader::new()
+            .grade(
+                &barcode(&[0, 255, 0, 0, 255, 255, 0], 0),
+                BarcodeFormat::Code128,
+            )
+            .unwrap();
+        assert_eq!(report.grade, Grade::A);
+        assert_eq!(report.scans[0].edges_found, report.scans[0].edges_expected);
+    }
+
+    #[test]
+    fn test_low_contrast_grades_down() {
+        let report = QualityGrader::new()
+            .grade(
+                &barcode(&[0, 255, 0, 0, 255, 255, 0], 120),
+                BarcodeFormat::Code128,
+            )
+            .unwrap();
+        assert_eq!(report.scans[0].grades.symbol_contrast, Grade::C);
+        assert_eq!(report.grade, Grade::C);
+    }
+
+    #[test]
+    fn test_rendered_ean13_grades() {
+        // zxing draws the guard bars longer, grading has to see the bar row anyway
+        let mut builder = BarcodeConfigBuilder::new();
+        builder.set_format(BarcodeFormat::EAN13);
+        let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+        let generated = Generator::new()
+            .render_barcode("400638133393", &config, "", 300.0)
+            .unwrap();
+        let report = QualityGrader::new()
+            .grade(&generated, BarcodeFormat::EAN13)
+            .unwrap();
+        assert_eq!(report.grade, Grade::A);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt::Write;
+
+use image::Rgb;
+
+use crate::generator::{
+    calculator::DimensionCalculator,
+    fonts::{FontRegistry, FALLBACK_FONT},
+    generator::GeneratedBarcode,
+};
+
+// Writes a generated barcode as SVG. Coordinates are the pixels of the raster buffer,
+// the document itself is sized in mm so it prints at the configured dimensions
+pub struct SvgRenderer {}
+
+impl SvgRenderer {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn render(&self, barcode: &GeneratedBarcode, dpi: f32) -> anyhow::Result<String> {
+        let calculator = DimensionCalculator::new();
+        let width_px = barcode.buffer.width();
+        let height_px = barcode.buffer.height();
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" width="{}mm" height="{}mm" viewBox="0 0 {} {}">"#,
+            calculator.px_to_mm(width_px, dpi),
+            calculator.px_to_mm(height_px, dpi),
+            width_px,
+            height_px
+        )?;
+        if !barcode.transparent_background {
+            writeln!(
+                svg,
+                r#"<rect width="100%" height="100%" fill="{}"/>"#,
+                hex_color(barcode.background)
+            )?;
+        }
+
+        // One path with a rectangle per run of dark modules, quiet zones are part of the symbol
+        let mut path = String::new();
+        for (left, top, width, height) in barcode.dark_rects() {
+            write!(path, "M{} {}h{}v{}h-{}Z", left, top, width, height, width)?;
+        }
+        writeln!(
+            svg,
+            r#"<path fill="{}" d="{}"/>"#,
+            hex_color(barcode.foreground),
+            path
+        )?;
+
+        for text in &barcode.texts {
+            let font_metrics = FontRegistry::global().load(&text.font)?.em;
+            let text_size = text.text_size as f32;
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="{}" font-size="{}" fill="{}">{}</text>"#,
+                text.x,
+                text.y as f32 + text_size * font_metrics.ascent_per_height,
+                escape_xml(&font_family(&text.font)),
+                text_size * font_metrics.em_per_height,
+                hex_color(text.text_color),
+                escape_xml(&text.text)
+            )?;
+        }
+
+        writeln!(svg, "</svg>")?;
+        Ok(svg)
+    }
+}
+
+// The font is not embedded, viewers without the family get the one the raster texts fall back to
+fn font_family(font: &str) -> String {
+    if font.eq_ignore_ascii_case(FALLBACK_FONT) {
+        format!("{}, sans-serif", FALLBACK_FONT)
+    } else {
+        format!("{}, {}, sans-serif", font, FALLBACK_FONT)
+    }
+}
+
+fn hex_color(color: Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::barcode_config::{
+        BarcodeConfigBuilder, BarcodeConfigInternal, LengthUnit, TextPosition, XDimension,
+    };
+    use crate::generator::generator::Generator;
+    use zxingcpp::BarcodeFormat;
+
+    // Code128 with 0.33 mm modules, 4 pixels at 300 DPI
+    fn builder() -> BarcodeConfigBuilder {
+        let mut builder = BarcodeConfigBuilder::new();
+        builder
+            .set_format(BarcodeFormat::Code128)
+            .set_x_dimension(Some(XDimension {
+                value: 0.33,
+                unit: LengthUnit::Mm,
+            }));
+        builder
+    }
+
+    fn render(builder: &BarcodeConfigBuilder, quiet_zones: bool) -> (GeneratedBarcode, String) {
+        let mut config = builder.build();
+        config.quiet_zones = quiet_zones;
+        let config: BarcodeConfigInternal = config.try_into().unwrap();
+        let generated = Generator::new()
+            .render_barcode("ABC123", &config, "", 300.0)
+            .unwrap();
+        let svg = SvgRenderer::new().render(&generated, 300.0).unwrap();
+        (generated, svg)
+    }
+
+    // Value of the first attribute with the name
+    fn attribute<'a>(svg: &'a str, name: &str) -> &'a str {
+        let start = svg.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+        let end = start + svg[start..].find('"').unwrap();
+        &svg[start..end]
+    }
+
+    // x of the first bar in the path
+    fn first_bar_x(svg: &str) -> f32 {
+        let path = attribute(svg, "d");
+        path[1..path.find(' ').unwrap()].parse().unwrap()
+    }
+
+    #[test]
+    fn test_size_in_mm() {
+        let (generated, svg) = render(&builder(), true);
+        for (name, px) in [
+            ("width", generated.buffer.width()),
+            ("height", generated.buffer.height()),
+        ] {
+            let value = attribute(&svg, name);
+            let mm: f32 = value.strip_suffix("mm").unwrap().parse().unwrap();
+            assert!((mm - px as f32 * 25.4 / 300.0).abs() < 0.001);
+        }
+        assert_eq!(
+            attribute(&svg, "viewBox"),
+            format!(
+                "0 0 {} {}",
+                generated.buffer.width(),
+                generated.buffer.height()
+            )
+        );
+    }
+
+    #[test]
+    fn test_quiet_zones() {
+        let (_, svg) = render(&builder(), false);
+        let (generated, quiet_svg) = render(&builder(), true);
+        // Code128 needs 10 modules of quiet zone on each side
+        assert!(first_bar_x(&quiet_svg) >= first_bar_x(&svg) + 40.0);
+        let (left, _, width, _) = *generated.dark_rects().last().unwrap();
+        assert!(left + width <= (generated.buffer.width() - 40) as f32);
+    }
+
+    #[test]
+    fn test_texts_and_colors() {
+        let mut builder = builder();
+        builder
+            .set_colors(Rgb([0, 0, 128]), Rgb([255, 255, 240]))
+            .add_text("Size <M> & co", Rgb([200, 0, 0]), 20, TextPosition::Lower);
+        let (_, svg) = render(&builder, true);
+        assert!(svg.contains(r##"<rect width="100%" height="100%" fill="#fffff0"/>"##));
+        assert_eq!(
+            attribute(&svg[svg.find("<path").unwrap()..], "fill"),
+            "#000080"
+        );
+
+        let text = &svg[svg.find("<text").unwrap()..];
+        assert_eq!(attribute(text, "fill"), "#c80000");
+        assert_eq!(attribute(text, "font-family"), "DejaVuSans, sans-serif");
+        assert!(text.contains(">Size &lt;M&gt; &amp; co</text>"));
+    }
+
+    #[test]
+    fn test_unknown_font_lists_fallback() {
+        assert_eq!(
+            font_family("Missing Family"),
+            "Missing Family, DejaVuSans, sans-serif"
+        );
+    }
+}
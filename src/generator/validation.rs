@@ -0,0 +1,464 @@
+use serde::Serialize;
+use zxingcpp::BarcodeFormat;
+
+use crate::generator::{quality::Grade, verification::VerificationFailure};
+
+// Validates barcode data before it reaches zxing so errors can point at the offending character.
+// Formats with a GS1 check digit accept the data with or without it, a missing check digit is appended.
+pub struct Validator {
+    // ITF values are GS1 ITF-14 numbers with a check digit, otherwise any even length is fine
+    itf14: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ValidationReason {
+    Empty,
+    TooShort { min: usize, actual: usize },
+    TooLong { max: usize, actual: usize },
+    InvalidLength { allowed: Vec<usize>, actual: usize },
+    InvalidCharacter { character: char },
+    InvalidCheckDigit { expected: u32, actual: u32 },
+    OddLength,
+    InvalidNumberSystem { digit: char },
+    MissingStartStop,
+    EncoderRejected { message: String },
+    // Generated, but the image did not read back as the value
+    VerificationFailed { failure: VerificationFailure },
+    // Estimated print quality below the minimum asked for
+    GradeTooLow { grade: Grade, minimum: Grade },
+    // A minimum grade was asked for but the barcode could not be graded
+    GradingFailed { message: String },
+    // Another row is written to the same file, row is the zero based index of that row
+    DuplicateFileName { file_name: String, row: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub field: String,
+    pub position: Option<usize>,
+    pub reason: ValidationReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationErrors {
+    pub errors: Vec<ValidationError>,
+}
+
+impl std::fmt::Display for ValidationReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationReason::Empty => write!(f, "value is empty"),
+            ValidationReason::TooShort { min, actual } => {
+                write!(
+                    f,
+                    "too short, {} characters but at least {} needed",
+                    actual, min
+                )
+            }
+            ValidationReason::TooLong { max, actual } => {
+                write!(
+                    f,
+                    "too long, {} characters but at most {} allowed",
+                    actual, max
+                )
+            }
+            ValidationReason::InvalidLength { allowed, actual } => {
+                write!(f, "length {} is not one of {:?}", actual, allowed)
+            }
+            ValidationReason::InvalidCharacter { character } => {
+                write!(f, "character {:?} is not allowed", character)
+            }
+            ValidationReason::InvalidCheckDigit { expected, actual } => {
+                write!(f, "check digit is {} but should be {}", actual, expected)
+            }
+            ValidationReason::OddLength => write!(f, "needs an even number of digits"),
+            ValidationReason::InvalidNumberSystem { digit } => {
+                write!(f, "number system must be 0 or 1, got {}", digit)
+            }
+            ValidationReason::MissingStartStop => {
+                write!(f, "must start and end with one of A, B, C or D")
+            }
+            ValidationReason::EncoderRejected { message } => write!(f, "{}", message),
+            ValidationReason::VerificationFailed { failure } => {
+                write!(f, "barcode does not read back: {}", failure)
+            }
+            ValidationReason::GradeTooLow { grade, minimum } => {
+                write!(f, "print quality grade {} is below {}", grade, minimum)
+            }
+            ValidationReason::GradingFailed { message } => {
+                write!(f, "print quality could not be graded: {}", message)
+            }
+            ValidationReason::DuplicateFileName { file_name, row } => {
+                write!(
+                    f,
+                    "file {} is already written by row {}",
+                    file_name,
+                    row + 1
+                )
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some(position) => write!(
+                f,
+                "{} at position {}: {}",
+                self.field, position, self.reason
+            ),
+            None => write!(f, "{}: {}", self.field, self.reason),
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self.errors.iter().map(|e| e.to_string()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+// Length and characters allowed for the data of one format
+struct Rule {
+    min: usize,
+    max: usize,
+    // Length is counted in UTF-8 bytes for the 2D codes, characters otherwise
+    count_bytes: bool,
+    allowed: fn(char) -> bool,
+}
+
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit()
+}
+
+fn is_ascii(c: char) -> bool {
+    c.is_ascii()
+}
+
+fn is_any(_: char) -> bool {
+    true
+}
+
+fn is_code39(c: char) -> bool {
+    c.is_ascii_digit() || c.is_ascii_uppercase() || "-. $/+%".contains(c)
+}
+
+fn is_codabar(c: char) -> bool {
+    c.is_ascii_digit() || "-$:/.+ABCD".contains(c)
+}
+
+fn rule(format: BarcodeFormat) -> Rule {
+    let (min, max, count_bytes, allowed): (usize, usize, bool, fn(char) -> bool) = match format {
+        // Linear codes without a length limit in their spec are only limited by the symbol width
+        BarcodeFormat::Code128 => (1, usize::MAX, false, is_ascii),
+        BarcodeFormat::Code39 => (1, usize::MAX, false, is_code39),
+        BarcodeFormat::Code93 => (1, usize::MAX, false, is_ascii),
+        BarcodeFormat::Codabar => (3, usize::MAX, false, is_codabar),
+        BarcodeFormat::EAN13 => (12, 13, false, is_digit),
+        BarcodeFormat::EAN8 => (7, 8, false, is_digit),
+        BarcodeFormat::UPCA => (11, 12, false, is_digit),
+        BarcodeFormat::UPCE => (7, 8, false, is_digit),
+        BarcodeFormat::ITF => (2, 125, false, is_digit),
+        BarcodeFormat::DataBar | BarcodeFormat::DataBarLimited => (13, 14, false, is_digit),
+        BarcodeFormat::DataBarExpanded => (2, 74, false, is_ascii),
+        BarcodeFormat::QRCode => (1, 2953, true, is_any),
+        BarcodeFormat::MicroQRCode => (1, 35, true, is_any),
+        BarcodeFormat::RMQRCode => (1, 361, true, is_any),
+        BarcodeFormat::DataMatrix => (1, 1556, true, is_any),
+        BarcodeFormat::PDF417 => (1, 1850, true, is_any),
+        BarcodeFormat::Aztec => (1, 1914, true, is_any),
+        BarcodeFormat::MaxiCode => (1, 138, true, is_any),
+        _ => (1, usize::MAX, false, is_any),
+    };
+    Rule {
+        min,
+        max,
+        count_bytes,
+        allowed,
+    }
+}
+
+// GS1 modulo 10 check digit, weights 3 and 1 starting from the rightmost data digit
+pub fn gs1_check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { d })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// UPC-E check digit is calculated over the expanded UPC-A number
+pub fn upce_check_digit(digits: &str) -> u32 {
+    let d: Vec<char> = digits.chars().collect();
+    let expanded: String = match d[6] {
+        '0' | '1' | '2' => [&d[0..3], &[d[6], '0', '0', '0', '0'], &d[3..6]].concat(),
+        '3' => [&d[0..4], &['0', '0', '0', '0', '0'], &d[4..6]].concat(),
+        '4' => [&d[0..5], &['0', '0', '0', '0', '0'], &d[5..6]].concat(),
+        _ => [&d[0..6], &['0', '0', '0', '0'], &d[6..7]].concat(),
+    }
+    .into_iter()
+    .collect();
+    gs1_check_digit(&expanded)
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self { itf14: false }
+    }
+
+    // Validate ITF values as ITF-14 shipping container codes
+    pub fn with_itf14(self) -> Self {
+        Self { itf14: true }
+    }
+
+    // Returns the data to encode, with the check digit appended when it was left out
+    pub fn validate(
+        &self,
+        format: BarcodeFormat,
+        field: &str,
+        value: &str,
+    ) -> Result<String, ValidationErrors> {
+        let error = |position: Option<usize>, reason: ValidationReason| ValidationError {
+            field: field.to_string(),
+            position,
+            reason,
+        };
+
+        if value.is_empty() {
+            return Err(ValidationErrors {
+                errors: vec![error(None, ValidationReason::Empty)],
+            });
+        }
+
+        let rule = rule(format);
+        let mut errors: Vec<ValidationError> = value
+            .chars()
+            .enumerate()
+            .filter(|(_, c)| !(rule.allowed)(*c))
+            .map(|(i, c)| error(Some(i), ValidationReason::InvalidCharacter { character: c }))
+            .collect();
+
+        let length = if rule.count_bytes {
+            value.len()
+        } else {
+            value.chars().count()
+        };
+        if length < rule.min {
+            errors.push(error(
+                None,
+                ValidationReason::TooShort {
+                    min: rule.min,
+                    actual: length,
+                },
+            ));
+        } else if length > rule.max {
+            errors.push(error(
+                None,
+                ValidationReason::TooLong {
+                    max: rule.max,
+                    actual: length,
+                },
+            ));
+        }
+        if !errors.is_empty() {
+            return Err(ValidationErrors { errors });
+        }
+
+        let result = match format {
+            BarcodeFormat::EAN13 | BarcodeFormat::EAN8 | BarcodeFormat::UPCA => self
+                .check_digit(value, rule.max, gs1_check_digit)
+                .map_err(|(position, reason)| error(position, reason)),
+            BarcodeFormat::UPCE => {
+                if !value.starts_with(['0', '1']) {
+                    Err(error(
+                        Some(0),
+                        ValidationReason::InvalidNumberSystem {
+                            digit: value.chars().next().unwrap_or_default(),
+                        },
+                    ))
+                } else {
+                    self.check_digit(value, rule.max, upce_check_digit)
+                        .map_err(|(position, reason)| error(position, reason))
+                }
+            }
+            BarcodeFormat::DataBar | BarcodeFormat::DataBarLimited => {
+                if format == BarcodeFormat::DataBarLimited && !value.starts_with(['0', '1']) {
+                    Err(error(
+                        Some(0),
+                        ValidationReason::InvalidNumberSystem {
+                            digit: value.chars().next().unwrap_or_default(),
+                        },
+                    ))
+                } else {
+                    self.check_digit(value, rule.max, gs1_check_digit)
+                        .map_err(|(position, reason)| error(position, reason))
+                }
+            }
+            BarcodeFormat::ITF if self.itf14 => self
+                .check_digit(value, 14, gs1_check_digit)
+                .map_err(|(position, reason)| error(position, reason)),
+            BarcodeFormat::ITF if length % 2 == 1 => Err(error(None, ValidationReason::OddLength)),
+            BarcodeFormat::Codabar => {
+                let is_start_stop = |c: Option<char>| c.is_some_and(|c| "ABCD".contains(c));
+                let inner_start_stop = value
+                    .chars()
+                    .enumerate()
+                    .skip(1)
+                    .take(length.saturating_sub(2))
+                    .find(|(_, c)| "ABCD".contains(*c));
+                if !is_start_stop(value.chars().next()) || !is_start_stop(value.chars().last()) {
+                    Err(error(None, ValidationReason::MissingStartStop))
+                } else if let Some((i, c)) = inner_start_stop {
+                    Err(error(
+                        Some(i),
+                        ValidationReason::InvalidCharacter { character: c },
+                    ))
+                } else {
+                    Ok(value.to_string())
+                }
+            }
+            _ => Ok(value.to_string()),
+        };
+
+        result.map_err(|e| ValidationErrors { errors: vec![e] })
+    }
+
+    // Appends the check digit when value is one digit short of full_length, otherwise verifies it
+    fn check_digit(
+        &self,
+        value: &str,
+        full_length: usize,
+        calculate: fn(&str) -> u32,
+    ) -> Result<String, (Option<usize>, ValidationReason)> {
+        let length = value.len();
+        if length == full_length - 1 {
+            return Ok(format!("{}{}", value, calculate(value)));
+        }
+        if length != full_length {
+            return Err((
+                None,
+                ValidationReason::InvalidLength {
+                    allowed: vec![full_length - 1, full_length],
+                    actual: length,
+                },
+            ));
+        }
+        let expected = calculate(&value[..length - 1]);
+        let actual = value[length - 1..].parse::<u32>().unwrap_or_default();
+        if expected != actual {
+            return Err((
+                Some(length - 1),
+                ValidationReason::InvalidCheckDigit { expected, actual },
+            ));
+        }
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_digit_appended() {
+        let validator = Validator::new();
+        assert_eq!(
+            validator
+                .validate(BarcodeFormat::EAN13, "data", "400638133393")
+                .unwrap(),
+            "4006381333931"
+        );
+        assert_eq!(
+            validator
+                .validate(BarcodeFormat::EAN8, "data", "9638507")
+                .unwrap(),
+            "96385074"
+        );
+        assert_eq!(
+            validator
+                .validate(BarcodeFormat::UPCA, "data", "03600029145")
+                .unwrap(),
+            "036000291452"
+        );
+        assert_eq!(
+            validator
+                .validate(BarcodeFormat::UPCE, "data", "0425261")
+                .unwrap(),
+            "04252614"
+        );
+        assert_eq!(
+            Validator::new()
+                .with_itf14()
+                .validate(BarcodeFormat::ITF, "data", "1540014128876")
+                .unwrap(),
+            "15400141288763"
+        );
+    }
+
+    #[test]
+    fn test_wrong_check_digit() {
+        let errors = Validator::new()
+            .validate(BarcodeFormat::EAN13, "data", "4006381333932")
+            .unwrap_err();
+        assert_eq!(
+            errors.errors,
+            vec![ValidationError {
+                field: "data".to_string(),
+                position: Some(12),
+                reason: ValidationReason::InvalidCheckDigit {
+                    expected: 1,
+                    actual: 2
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_characters_reported_by_position() {
+        let errors = Validator::new()
+            .validate(BarcodeFormat::Code39, "value", "AB*c")
+            .unwrap_err();
+        let positions: Vec<Option<usize>> = errors.errors.iter().map(|e| e.position).collect();
+        assert_eq!(positions, vec![Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn test_itf_odd_length() {
+        let errors = Validator::new()
+            .validate(BarcodeFormat::ITF, "data", "12345")
+            .unwrap_err();
+        assert_eq!(errors.errors[0].reason, ValidationReason::OddLength);
+
+        // Plain interleaved 2 of 5 has no check digit, only ITF-14 does
+        let validator = Validator::new();
+        assert_eq!(
+            validator
+                .validate(BarcodeFormat::ITF, "data", "12345678901234")
+                .unwrap(),
+            "12345678901234"
+        );
+        assert!(validator
+            .with_itf14()
+            .validate(BarcodeFormat::ITF, "data", "12345678901234")
+            .is_err());
+    }
+
+    #[test]
+    fn test_long_code128_and_code39() {
+        let validator = Validator::new();
+        let value = "A".repeat(120);
+        assert!(validator
+            .validate(BarcodeFormat::Code128, "data", &value)
+            .is_ok());
+        assert!(validator
+            .validate(BarcodeFormat::Code39, "data", &value)
+            .is_ok());
+    }
+}
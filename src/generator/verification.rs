@@ -0,0 +1,189 @@
+use image::{GrayImage, Pixel};
+use serde::Serialize;
+use zxingcpp::{BarcodeFormat, ImageFormat, ImageView, TextMode};
+
+use crate::generator::{
+    barcode_config::BarcodeFormatWrapper,
+    generator::{on_white, GeneratedBarcode},
+};
+
+// Why a generated barcode did not read back as what was encoded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum VerificationFailure {
+    NotDecoded,
+    WrongFormat { expected: String, actual: String },
+    WrongText { expected: String, actual: String },
+}
+
+impl std::fmt::Display for VerificationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerificationFailure::NotDecoded => write!(f, "no barcode could be decoded"),
+            VerificationFailure::WrongFormat { expected, actual } => {
+                write!(f, "decoded as {} instead of {}", actual, expected)
+            }
+            VerificationFailure::WrongText { expected, actual } => {
+                write!(f, "decoded \"{}\" instead of \"{}\"", actual, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerificationFailure {}
+
+// Reads generated barcodes back with the zxing reader, so unreadable labels are caught before printing
+pub struct Verifier {}
+
+impl Verifier {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    // The rendered buffer must decode as format with exactly the encoded value.
+    // All formats are read, so a symbol a scanner would take for another format is caught.
+    // Texts around the symbol stay in the image, the reader has to cope with them like a scanner would
+    pub fn verify(
+        &self,
+        barcode: &GeneratedBarcode,
+        format: BarcodeFormat,
+    ) -> Result<(), VerificationFailure> {
+        let gray = to_gray(barcode);
+        let image =
+            ImageView::from_slice(gray.as_raw(), gray.width(), gray.height(), ImageFormat::Lum)
+                .map_err(|_| VerificationFailure::NotDecoded)?;
+        // Plain text, the default adds parentheses around GS1 application identifiers
+        let decoded = zxingcpp::read()
+            .text_mode(TextMode::Plain)
+            .from(&image)
+            .map_err(|_| VerificationFailure::NotDecoded)?;
+        let expected_text = element_string(format, &barcode.value);
+        let decoded: Vec<(BarcodeFormat, String)> = decoded
+            .iter()
+            .map(|decoded| match decoded.format() {
+                // An EAN-13 starting with 0 is the same symbol as a UPC-A, which the reader reports
+                BarcodeFormat::UPCA if format == BarcodeFormat::EAN13 => {
+                    (BarcodeFormat::EAN13, format!("0{}", decoded.text()))
+                }
+                found_format => (found_format, element_string(found_format, &decoded.text())),
+            })
+            .collect();
+        // Prefer a symbol with the expected text, any other one explains the mismatch
+        let (found_format, found_text) = decoded
+            .iter()
+            .find(|(_, text)| *text == expected_text)
+            .or(decoded.first())
+            .cloned()
+            .ok_or(VerificationFailure::NotDecoded)?;
+        if found_format != format {
+            return Err(VerificationFailure::WrongFormat {
+                expected: BarcodeFormatWrapper::from(format).format,
+                actual: BarcodeFormatWrapper::from(found_format).format,
+            });
+        }
+        if found_text != expected_text {
+            return Err(VerificationFailure::WrongText {
+                expected: barcode.value.clone(),
+                actual: found_text,
+            });
+        }
+        Ok(())
+    }
+}
+
+// GS1 DataBar values compared without their syntax. DataBar and DataBar Limited GTINs can read
+// back with the (01) identifier, Expanded values are entered with [AI] brackets and read with
+// group separators after variable length fields
+fn element_string(format: BarcodeFormat, text: &str) -> String {
+    match format {
+        BarcodeFormat::DataBar | BarcodeFormat::DataBarLimited if text.len() == 16 => {
+            text.strip_prefix("01").unwrap_or(text).to_string()
+        }
+        BarcodeFormat::DataBarExpanded => text
+            .chars()
+            .filter(|c| !matches!(c, '[' | ']' | '\u{1d}'))
+            .collect(),
+        _ => text.to_string(),
+    }
+}
+
+// Grayscale of the buffer as printed on white paper, transparent parts become white
+fn to_gray(barcode: &GeneratedBarcode) -> GrayImage {
+    let buffer = &barcode.buffer;
+    GrayImage::from_fn(buffer.width(), buffer.height(), |x, y| {
+        on_white(buffer.get_pixel(x, y)).to_luma()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::{
+        barcode_config::{BarcodeConfigBuilder, BarcodeConfigInternal},
+        generator::Generator,
+    };
+
+    fn render(format: BarcodeFormat, data: &str) -> GeneratedBarcode {
+        let mut builder = BarcodeConfigBuilder::new();
+        builder.set_format(format);
+        let config: BarcodeConfigInternal = builder.build().try_into().unwrap();
+        Generator::new()
+            .render_barcode(data, &config, "", 300.0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reads_back_encoded_value() {
+        let verifier = Verifier::new();
+        let code128 = render(BarcodeFormat::Code128, "ABC-1234");
+        assert_eq!(verifier.verify(&code128, BarcodeFormat::Code128), Ok(()));
+        let ean13 = render(BarcodeFormat::EAN13, "400638133393");
+        assert_eq!(verifier.verify(&ean13, BarcodeFormat::EAN13), Ok(()));
+        // Read as UPC-A by the reader
+        let ean13 = render(BarcodeFormat::EAN13, "003600029145");
+        assert_eq!(verifier.verify(&ean13, BarcodeFormat::EAN13), Ok(()));
+    }
+
+    #[test]
+    fn test_reads_back_gs1_databar() {
+        let verifier = Verifier::new();
+        for format in [BarcodeFormat::DataBar, BarcodeFormat::DataBarLimited] {
+            let databar = render(format, "0950110153000");
+            assert_eq!(verifier.verify(&databar, format), Ok(()), "{:?}", format);
+        }
+        // The variable length batch is followed by a group separator when read
+        let expanded = render(
+            BarcodeFormat::DataBarExpanded,
+            "[01]09501101530003[10]AB12[21]XY",
+        );
+        assert_eq!(
+            verifier.verify(&expanded, BarcodeFormat::DataBarExpanded),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_value_mismatch() {
+        let mut barcode = render(BarcodeFormat::Code128, "ABC-1234");
+        barcode.value = "ABC-1235".to_string();
+        assert_eq!(
+            Verifier::new().verify(&barcode, BarcodeFormat::Code128),
+            Err(VerificationFailure::WrongText {
+                expected: "ABC-1235".to_string(),
+                actual: "ABC-1234".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_format_mismatch() {
+        // A Code 39 symbol where Code 128 was expected is read, but as the wrong format
+        let barcode = render(BarcodeFormat::Code39, "ABC-1234");
+        assert_eq!(
+            Verifier::new().verify(&barcode, BarcodeFormat::Code128),
+            Err(VerificationFailure::WrongFormat {
+                expected: BarcodeFormatWrapper::from(BarcodeFormat::Code128).format,
+                actual: BarcodeFormatWrapper::from(BarcodeFormat::Code39).format,
+            })
+        );
+    }
+}
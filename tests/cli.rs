@@ -0,0 +1,115 @@
+// Runs barcode-cli like a CI pipeline would and checks its exit codes and JSON summaries
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use app_lib::generator::{
+    barcode_config::BarcodeConfigBuilder, database::Database, templates::Template,
+};
+use zxingcpp::BarcodeFormat;
+
+const TEMPLATE_ID: &str = "ean13";
+
+// Empty working directory with an EAN-13 template and a CSV whose second row is too short
+fn work_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("barcode_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    let config = BarcodeConfigBuilder::new()
+        .set_format(BarcodeFormat::EAN13)
+        .build();
+    let template = Template::with_id(
+        config,
+        "EAN-13".to_string(),
+        String::new(),
+        TEMPLATE_ID.to_string(),
+    );
+    Database::new(dir.join("templates.json").to_string_lossy().to_string())
+        .save_template(&template)
+        .unwrap();
+    fs::write(dir.join("rows.csv"), "value\n400638133393\n123\n").unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> (i32, serde_json::Value) {
+    let output = Command::new(env!("CARGO_BIN_EXE_barcode-cli"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .unwrap();
+    let summary = serde_json::from_slice(&output.stdout).unwrap_or(serde_json::Value::Null);
+    (output.status.code().unwrap(), summary)
+}
+
+fn bulk_args(extra: &[&'static str]) -> Vec<&'static str> {
+    let mut args = vec![
+        "bulk",
+        "--template",
+        TEMPLATE_ID,
+        "--csv",
+        "rows.csv",
+        "--output-dir",
+        "out",
+    ];
+    args.extend_from_slice(extra);
+    args
+}
+
+#[test]
+fn test_bulk_reports_failed_rows() {
+    let dir = work_dir("skip");
+    let (code, summary) = run(&dir, &bulk_args(&[]));
+    assert_eq!(code, 3);
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["data"]["generated"], 1);
+    let failed = summary["data"]["failed"].as_array().unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["row"], 1);
+    assert_eq!(failed[0]["value"], "123");
+    // The check digit is added to the generated value
+    assert!(dir.join("out").join("4006381333931.png").exists());
+}
+
+#[test]
+fn test_abort_keeps_failed_rows() {
+    let dir = work_dir("abort");
+    let (code, summary) = run(&dir, &bulk_args(&["--abort"]));
+    assert_eq!(code, 1);
+    assert_eq!(summary["success"], false);
+    assert_eq!(summary["data"]["generated"], 0);
+    assert_eq!(summary["data"]["failed"][0]["row"], 1);
+}
+
+#[test]
+fn test_generate_exit_codes() {
+    let dir = work_dir("generate");
+    let (code, summary) = run(
+        &dir,
+        &[
+            "generate",
+            "--template",
+            TEMPLATE_ID,
+            "--data",
+            "400638133393",
+            "--output",
+            "one.svg",
+        ],
+    );
+    assert_eq!(code, 0);
+    assert_eq!(summary["success"], true);
+    assert_eq!(summary["data"]["value"], "4006381333931");
+    assert!(dir.join("one.svg").exists());
+
+    let (code, summary) = run(
+        &dir,
+        &["generate", "--template", "missing", "--output", "two.png"],
+    );
+    assert_eq!(code, 1);
+    assert_eq!(summary["success"], false);
+    assert!(!dir.join("two.png").exists());
+
+    // Argument errors come from clap
+    let (code, _) = run(&dir, &["generate", "--output", "three.png"]);
+    assert_eq!(code, 2);
+}